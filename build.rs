@@ -0,0 +1,18 @@
+use std::process::Command;
+
+// Embeds the current commit as `RIKKA_GIT_HASH`, for `stats`. Builds outside
+// of a git checkout just don't have it.
+fn main() {
+    let hash = Command::new("git")
+        .args(&["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok());
+
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=RIKKA_GIT_HASH={}", hash.trim());
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -0,0 +1,300 @@
+## Formatting. `fmt-date` is a chrono strftime pattern.
+
+fmt-date = %d.%m.%Y
+fmt-number-group = .
+fmt-date-relative = { $date } ({ $relative })
+language-name = Deutsch
+
+time-just-now = gerade eben
+time-seconds-ago = { $count ->
+    [one] vor einer Sekunde
+   *[other] vor { $count } Sekunden
+}
+time-minutes-ago = { $count ->
+    [one] vor einer Minute
+   *[other] vor { $count } Minuten
+}
+time-hours-ago = { $count ->
+    [one] vor einer Stunde
+   *[other] vor { $count } Stunden
+}
+time-days-ago = { $count ->
+    [one] vor einem Tag
+   *[other] vor { $count } Tagen
+}
+time-months-ago = { $count ->
+    [one] vor einem Monat
+   *[other] vor { $count } Monaten
+}
+time-years-ago = { $count ->
+    [one] vor einem Jahr
+   *[other] vor { $count } Jahren
+}
+
+## Errors and framework replies
+
+error-generic = Ein Fehler ist aufgetreten: { $error }
+
+requires-owner = Nur Bot-Besitzer dürfen diesen Befehl verwenden.
+requires-guild = Dieser Befehl kann nur auf einem Server verwendet werden.
+requires-dm = Dieser Befehl kann nur in Direktnachrichten verwendet werden.
+requires-permissions = Dir fehlen folgende Berechtigungen: { $permissions }
+requirement-owner = Bot-Besitzer
+requirement-guild = Nur auf Servern
+requirement-dm = Nur in Direktnachrichten
+maintenance-active = Der Bot wird gerade gewartet, versuche es später noch einmal.
+
+arg-missing = Fehlendes Argument `{ $usage }`.
+arg-invalid = Ungültiges Argument `{ $usage }`: erwartet wurde { $expected }, erhalten `{ $value }`.
+arg-malformed = Ungültiges Argument `{ $usage }`: erwartet wurde { $expected }.
+arg-usage = Verwendung: `{ $usage }`
+expected-number = eine ganze Zahl
+expected-user-id = eine Benutzererwähnung oder -ID
+expected-channel-id = eine Kanalerwähnung oder -ID
+expected-role-id = eine Rollenerwähnung oder -ID
+expected-user = ein Benutzer, den ich sehen kann
+expected-member = ein Mitglied dieses Servers
+expected-channel = ein Kanal auf diesem Server
+expected-role = eine Rolle auf diesem Server
+expected-duration = eine Dauer wie `1h30m`
+expected-embed = ein Embed als JSON oder als Zeilen im Format `schlüssel: wert`
+expected-range = eine Zahl von { $start } bis { $end }
+expected-one-of = eines von { $options }
+
+unknown-command = Unbekannter Befehl `{ $name }`
+unknown-command-suggestion = Unbekannter Befehl `{ $name }`, meintest du `{ $suggestion }`?
+unknown-option = Unbekannte Option `{ $option }`. Erwartet wurde eines von { $options }
+unknown-option-suggestion = Unbekannte Option `{ $option }`, meintest du `{ $suggestion }`?
+subcommands = Unterbefehle:
+
+## Help
+
+help-author = Rikka v3 Befehlshilfe
+help-title = Tritt unserem Server für mehr Informationen bei
+help-description = Gib `{ $prefix }help [befehl]` ein, um Details zur Verwendung zu sehen
+section-general = Allgemein
+section-fun = Spaß
+section-info = Info
+section-moderation = Moderation
+section-owner = Besitzer
+help-sent-dm = Ich habe dir die Hilfe per DM geschickt
+help-dm-failed = Ich konnte dir keine DM schicken. Prüfe, ob du DMs von Servermitgliedern erlaubst.
+help-search-title = Befehle passend zu „{ $query }“
+help-search-empty = Keine Befehle passen zu „{ $query }“
+help-usage = Verwendung
+help-aliases = Aliase
+help-subcommands = Unterbefehle
+help-examples = Beispiele
+help-requires = Voraussetzungen
+help-cooldown = Abklingzeit
+help-cooldown-value = { $seconds ->
+    [one] 1 Sekunde
+   *[other] { $seconds } Sekunden
+}
+
+## Befehlshilfe. Schlüssel sind `help-`, gefolgt vom Pfad des Befehls mit
+## Bindestrichen statt Leerzeichen, dann `-description` oder `-detailed`.
+
+help-help-description = Listet Befehle auf oder erklärt, wie man einen verwendet
+help-help-detailed = Es werden nur Befehle aufgelistet, die du hier verwenden kannst. Unterbefehle lassen sich über ihren vollständigen Pfad nachschlagen, `help search <wörter>` findet Befehle anhand dessen, was sie tun, und `--dm` schickt dir die Hilfe stattdessen per DM, mit allen Befehlen, die du irgendwo verwenden kannst.
+help-ping-description = Prüft, ob der Bot noch lebt
+help-ping-detailed = Zeigt, wie schnell Discords API, jeder Gateway-Shard, der Speicher und alle anderen Dienste, von denen Befehle abhängen, antworten.
+help-stats-description = Zeigt Laufzeit, Nutzung und Ressourcenstatistiken des Bots
+help-userinfo-description = Zeigt Informationen über einen Benutzer
+help-userinfo-detailed = Standardmäßig du selbst. Auf einem Server werden auch das Beitrittsdatum und die Rollen angezeigt.
+help-serverinfo-description = Zeigt Informationen über diesen Server
+help-roleinfo-description = Zeigt Informationen über eine Rolle
+help-channelinfo-description = Zeigt Informationen über einen Kanal
+help-channelinfo-detailed = Standardmäßig der Kanal, in dem der Befehl verwendet wird.
+help-avatar-description = Zeigt den Avatar eines Benutzers
+help-avatar-detailed = Standardmäßig du selbst.
+help-played-description = Zeigt, welche Spiele ein Benutzer wie lange gespielt hat
+help-played-detailed = Standardmäßig du selbst. Spiele werden nach Spielzeit sortiert, außer `--sort` sagt etwas anderes, `--limit` zeigt nur die ersten paar, und lange Listen werden in Seiten aufgeteilt, die mit `--page` gewählt werden. `played top` zeigt stattdessen die meistgespielten Spiele des Servers und wer sie am meisten gespielt hat.
+help-say-description = Lässt den Bot etwas sagen
+help-say-detailed = Erwähnungen im Text pingen niemanden. `--delete` löscht deine Nachricht, und `--embed` liest den Text als Embed, entweder als JSON oder als `schlüssel: wert`-Zeilen mit den Schlüsseln `title`, `description`, `url`, `color`, `footer`, `image`, `thumbnail` und `field` (als `field: name: wert`).
+help-audit-description = Kürzlich ausgeführte Befehle nachschlagen
+help-audit-detailed = Zeigt standardmäßig bis zu 10 Einträge, höchstens 25.
+help-audit-guild-description = Kürzlich auf diesem Server ausgeführte Befehle
+help-audit-user-description = Kürzlich von einem Benutzer ausgeführte Befehle
+help-log-description = Serveraktivität in einem Kanal protokollieren
+help-log-detailed = Protokolle werden über einen Webhook im gewählten Kanal gepostet.
+help-log-message-description = Bearbeitete und gelöschte Nachrichten protokollieren
+help-log-message-enable-description = In einem Kanal protokollieren, standardmäßig in diesem
+help-log-message-disable-description = Nachrichten nicht mehr protokollieren
+help-settings-description = Den Bot für diesen Server einrichten
+help-settings-detailed = Verwende eine Einstellung ohne Wert, um zu sehen, worauf sie gerade gesetzt ist.
+help-settings-suggestions-description = Ähnliche Befehle vorschlagen, wenn ein unbekannter verwendet wird
+help-settings-language-description = Die Sprache, in der der Bot antwortet
+help-commands-description = Registrierte Befehle verwalten
+help-commands-detailed = Deaktivierte Befehle bleiben registriert, ignorieren aber Nachrichten, bis sie wieder aktiviert werden.
+help-commands-list-description = Alle Befehle auflisten und ob sie aktiviert sind
+help-commands-enable-description = Einen deaktivierten Befehl wieder ausführen
+help-commands-disable-description = Einen Befehl nicht mehr ausführen, bis er aktiviert wird
+help-bot-description = Den laufenden Bot bedienen
+help-bot-detailed = Der Wartungsmodus hält an, bis der Bot neu startet. Eine von Hand gesetzte Präsenz ersetzt die rotierenden bis zu `bot rotate`.
+help-bot-presence-description = Status und Aktivität des Bots auf jedem Shard setzen, statt zu rotieren
+help-bot-rotate-description = Wieder durch die konfigurierten Präsenzen rotieren
+help-bot-restart-description = Einen Shard neu mit dem Gateway verbinden
+help-bot-reload-description = Die Konfiguration und die Einstellungen aller Server neu laden
+help-bot-maintenance-description = Nur Besitzer Befehle verwenden lassen, oder ohne Zustand umschalten
+help-bot-shutdown-description = Laufende Befehle beenden und den Bot stoppen
+
+## Commands
+
+ping-pong = Pong!
+ping-rest = REST
+ping-storage = Datenbank
+ping-object-storage = Objektspeicher
+ping-shards = Shards
+ping-shard = Shard { $id }: { $latency }
+ping-shards-more = …und { $count } weitere
+ping-ms = { $ms }ms
+ping-unknown = unbekannt
+ping-failed = fehlgeschlagen: { $error }
+ping-timed-out = Zeitüberschreitung
+
+stats-title = Bot-Statistiken
+stats-uptime = Laufzeit
+stats-version = Version
+stats-guilds = Server
+stats-channels = Kanäle
+stats-users = Benutzer
+stats-shards = Shards
+stats-memory = Speicher
+stats-memory-value = { $allocated } belegt, { $resident } resident
+stats-tasks = Handler-Tasks
+stats-tasks-value = { $running } laufend, { $queued } wartend
+stats-commands-run = Ausgeführte Befehle
+stats-unknown = unbekannt
+
+info-id = ID
+info-created = Erstellt
+info-none = Keine
+info-unknown = unbekannt
+info-more = { " " }und { $count } weitere
+info-bool = { $value ->
+    [true] Ja
+   *[false] Nein
+}
+userinfo-bot = Bot
+userinfo-nickname = Spitzname
+userinfo-joined = Beigetreten
+userinfo-join-position = Beitrittsposition
+userinfo-join-position-value = #{ $position } von { $total }
+userinfo-roles = Rollen ({ $count })
+serverinfo-owner = Besitzer
+serverinfo-members = Mitglieder
+serverinfo-channels = Kanäle
+serverinfo-roles = Rollen
+serverinfo-boosts = Boosts
+roleinfo-color = Farbe
+roleinfo-position = Position
+roleinfo-members = Mitglieder
+roleinfo-hoisted = Separat angezeigt
+roleinfo-mentionable = Erwähnbar
+roleinfo-managed = Von einer Integration verwaltet
+roleinfo-permissions = Berechtigungen
+channelinfo-type = Typ
+channelinfo-kind = { $kind ->
+    [category] Kategorie
+    [news] Ankündigungen
+    [voice] Sprachkanal
+   *[text] Textkanal
+}
+channelinfo-category = Kategorie
+channelinfo-position = Position
+channelinfo-nsfw = NSFW
+channelinfo-slowmode = Slowmode
+channelinfo-seconds = { $seconds }s
+channelinfo-bitrate = Bitrate
+channelinfo-kbps = { $kbps } kbps
+channelinfo-user-limit = Benutzerlimit
+avatar-title = Avatar von { $user }
+
+played-no-entries = Keine Einträge für { $user } gefunden
+played-seen = *Zuerst gesehen { $first_seen }, zuletzt aktualisiert { $last_updated }*
+played-games = Spiele
+played-total = Gesamt
+played-total-value = { $time } in { $games ->
+    [one] 1 Spiel
+   *[other] { $games } Spielen
+}
+played-page = Seite { $page }/{ $pages }
+played-top-title = Meistgespielte Spiele
+played-top-description = Über { $members ->
+    [one] 1 Mitglied
+   *[other] { $members } Mitglieder
+}
+played-top-sampled = Über { $members } zufällig ausgewählte von { $total } Mitgliedern
+played-top-game = Insgesamt { $total }. Top-Spieler: { $players }
+played-top-empty = Hier hat noch niemand etwas gespielt
+
+logs-enabled = Nachrichtenprotokoll in { $channel } aktiviert
+logs-disabled = Nachrichtenprotokoll deaktiviert
+logs-not-enabled = Das Nachrichtenprotokoll ist nicht aktiviert
+logs-message-update = Nachricht bearbeitet
+logs-message-deleted = Nachricht gelöscht
+logs-user = Benutzer
+logs-channel = Kanal
+logs-old-content = Alter Inhalt
+logs-new-content = Neuer Inhalt
+logs-deleted-content = Gelöschter Inhalt
+logs-webhook-name = Nachrichtenprotokoll
+logs-setting-changed = Einstellung geändert
+logs-setting = Einstellung
+logs-value = Wert
+logs-moderation = { $action ->
+    [ban] Mitglied gebannt
+    [unban] Mitglied entbannt
+    [kick] Mitglied gekickt
+    [mute] Mitglied stummgeschaltet
+    [unmute] Stummschaltung aufgehoben
+   *[warn] Mitglied verwarnt
+}
+logs-moderator = Moderator
+logs-target = Mitglied
+logs-reason = Grund
+
+settings-suggestions = { $enabled ->
+    [true] Befehlsvorschläge sind aktiviert
+   *[false] Befehlsvorschläge sind deaktiviert
+}
+settings-language = Die Sprache dieses Servers ist { $language }
+settings-language-unknown = Unbekannte Sprache `{ $language }`. Verfügbare Sprachen: { $languages }
+
+commands-list = Befehle
+commands-state = { $enabled ->
+    [true] aktiviert
+   *[false] deaktiviert
+}
+commands-enabled = `{ $name }` wurde aktiviert
+commands-disabled = `{ $name }` wurde deaktiviert
+commands-unknown = Es ist kein Befehl namens `{ $name }` registriert
+commands-protected = `{ $name }` kann nicht deaktiviert werden
+
+control-presence-set = Präsenz auf { $shards ->
+    [one] 1 Shard
+   *[other] { $shards } Shards
+} aktualisiert
+control-rotating = Präsenzen rotieren wieder, gerade wird `{ $presence }` angezeigt
+control-no-presences = Es sind keine Präsenzen zum Rotieren eingestellt
+control-unknown-shard = Es gibt keinen Shard { $id }
+control-shard-restarted = Shard { $id } neu gestartet
+control-reloaded = Konfiguration und Einstellungen für { $guilds ->
+    [one] 1 Server
+   *[other] { $guilds } Server
+} neu geladen
+control-maintenance = { $enabled ->
+    [true] Der Wartungsmodus ist an, nur Besitzer können Befehle verwenden
+   *[false] Der Wartungsmodus ist aus
+}
+control-shutdown = Fahre herunter...
+
+audit-title = Zuletzt ausgeführte Befehle
+audit-empty = Es wurden noch keine Befehle aufgezeichnet
+audit-entry = { $when }: { $user } führte `{ $command }` aus - { $outcome ->
+    [success] ok
+    [bad-argument] ungültiges Argument
+    [denied] verweigert
+   *[error] Fehler
+} ({ $ms }ms)
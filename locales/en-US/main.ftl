@@ -0,0 +1,300 @@
+## Formatting. `fmt-date` is a chrono strftime pattern.
+
+fmt-date = %B %-d, %Y
+fmt-number-group = ,
+fmt-date-relative = { $date } ({ $relative })
+language-name = English
+
+time-just-now = just now
+time-seconds-ago = { $count ->
+    [one] a second ago
+   *[other] { $count } seconds ago
+}
+time-minutes-ago = { $count ->
+    [one] a minute ago
+   *[other] { $count } minutes ago
+}
+time-hours-ago = { $count ->
+    [one] an hour ago
+   *[other] { $count } hours ago
+}
+time-days-ago = { $count ->
+    [one] a day ago
+   *[other] { $count } days ago
+}
+time-months-ago = { $count ->
+    [one] a month ago
+   *[other] { $count } months ago
+}
+time-years-ago = { $count ->
+    [one] a year ago
+   *[other] { $count } years ago
+}
+
+## Errors and framework replies
+
+error-generic = An error occurred: { $error }
+
+requires-owner = Only bot owners may use this command.
+requires-guild = This command can only be used in a server.
+requires-dm = This command can only be used in DMs.
+requires-permissions = You're missing the following permissions: { $permissions }
+requirement-owner = Bot owner
+requirement-guild = Server only
+requirement-dm = DMs only
+maintenance-active = The bot is down for maintenance, try again later.
+
+arg-missing = Missing argument `{ $usage }`.
+arg-invalid = Invalid argument `{ $usage }`: expected { $expected }, got `{ $value }`.
+arg-malformed = Invalid argument `{ $usage }`: expected { $expected }.
+arg-usage = Usage: `{ $usage }`
+expected-number = a whole number
+expected-user-id = a user mention or id
+expected-channel-id = a channel mention or id
+expected-role-id = a role mention or id
+expected-user = a user I can see
+expected-member = a member of this server
+expected-channel = a channel in this server
+expected-role = a role in this server
+expected-duration = a duration like `1h30m`
+expected-embed = an embed as JSON or `key: value` lines
+expected-range = a number from { $start } to { $end }
+expected-one-of = one of { $options }
+
+unknown-command = Unknown command `{ $name }`
+unknown-command-suggestion = Unknown command `{ $name }`, did you mean `{ $suggestion }`?
+unknown-option = Unknown option `{ $option }`. Expected one of { $options }
+unknown-option-suggestion = Unknown option `{ $option }`, did you mean `{ $suggestion }`?
+subcommands = Subcommands:
+
+## Help
+
+help-author = Rikka v3 Command Help
+help-title = Join our server for more information
+help-description = Type `{ $prefix }help [command]` for detailed usage information
+section-general = General
+section-fun = Fun
+section-info = Info
+section-moderation = Moderation
+section-owner = Owner
+help-sent-dm = Sent you a DM with the help
+help-dm-failed = I couldn't DM you. Check that you allow DMs from server members.
+help-search-title = Commands matching "{ $query }"
+help-search-empty = No commands match "{ $query }"
+help-usage = Usage
+help-aliases = Aliases
+help-subcommands = Subcommands
+help-examples = Examples
+help-requires = Requires
+help-cooldown = Cooldown
+help-cooldown-value = { $seconds ->
+    [one] 1 second
+   *[other] { $seconds } seconds
+}
+
+## Command help. Keys are `help-` followed by the command's path with
+## dashes for spaces, then `-description` or `-detailed`.
+
+help-help-description = Lists commands, or explains how to use one
+help-help-detailed = Only commands you can use here are listed. Subcommands can be looked up by their full path, `help search <words>` finds commands by what they do, and `--dm` sends the help to your DMs instead, listing every command you can use anywhere.
+help-ping-description = Checks that the bot is alive
+help-ping-detailed = Reports how long Discord's API, each gateway shard, storage and any other services commands depend on take to respond.
+help-stats-description = Shows uptime, usage and resource statistics for the bot
+help-userinfo-description = Shows information about a user
+help-userinfo-detailed = Defaults to yourself. In a server, also shows when they joined and their roles.
+help-serverinfo-description = Shows information about this server
+help-roleinfo-description = Shows information about a role
+help-channelinfo-description = Shows information about a channel
+help-channelinfo-detailed = Defaults to the channel the command is used in.
+help-avatar-description = Shows a user's avatar
+help-avatar-detailed = Defaults to yourself.
+help-played-description = Shows which games a user has played, and for how long
+help-played-detailed = Defaults to yourself. Games are sorted by playtime unless `--sort` says otherwise, `--limit` only shows the first few, and long lists are split into pages picked with `--page`. `played top` shows the server's most played games and who played them most instead.
+help-say-description = Makes the bot say something
+help-say-detailed = Mentions in the text never ping anyone. `--delete` removes your message, and `--embed` reads the text as an embed, either as JSON or as `key: value` lines with the keys `title`, `description`, `url`, `color`, `footer`, `image`, `thumbnail` and `field` (as `field: name: value`).
+help-audit-description = Look up recently run commands
+help-audit-detailed = Shows up to 10 entries by default, and at most 25.
+help-audit-guild-description = Commands recently run in this server
+help-audit-user-description = Commands recently run by a user
+help-log-description = Log server activity to a channel
+help-log-detailed = Logs are posted through a webhook in the chosen channel.
+help-log-message-description = Log edited and deleted messages
+help-log-message-enable-description = Start logging to a channel, defaulting to this one
+help-log-message-disable-description = Stop logging messages
+help-settings-description = Configure the bot for this server
+help-settings-detailed = Run a setting without a value to see what it's currently set to.
+help-settings-suggestions-description = Suggest similar commands when an unknown one is used
+help-settings-language-description = The language the bot replies in
+help-commands-description = Manage registered commands
+help-commands-detailed = Disabled commands stay registered but ignore messages until they're enabled again.
+help-commands-list-description = List every command and whether it's enabled
+help-commands-enable-description = Start handling a disabled command again
+help-commands-disable-description = Stop handling a command until it's enabled
+help-bot-description = Operate the running bot
+help-bot-detailed = Maintenance mode lasts until the bot restarts. A presence set by hand replaces the rotating ones until `bot rotate`.
+help-bot-presence-description = Set the bot's status and activity on every shard, instead of rotating
+help-bot-rotate-description = Go back to rotating through the configured presences
+help-bot-restart-description = Reconnect a shard to the gateway
+help-bot-reload-description = Reload the configuration and every server's settings
+help-bot-maintenance-description = Only let owners use commands, or toggle it with no state
+help-bot-shutdown-description = Finish running commands and stop the bot
+
+## Commands
+
+ping-pong = Pong!
+ping-rest = REST
+ping-storage = Storage
+ping-object-storage = Object storage
+ping-shards = Shards
+ping-shard = Shard { $id }: { $latency }
+ping-shards-more = …and { $count } more
+ping-ms = { $ms }ms
+ping-unknown = unknown
+ping-failed = failed: { $error }
+ping-timed-out = timed out
+
+stats-title = Bot statistics
+stats-uptime = Uptime
+stats-version = Version
+stats-guilds = Servers
+stats-channels = Channels
+stats-users = Users
+stats-shards = Shards
+stats-memory = Memory
+stats-memory-value = { $allocated } allocated, { $resident } resident
+stats-tasks = Handler tasks
+stats-tasks-value = { $running } running, { $queued } queued
+stats-commands-run = Commands run
+stats-unknown = unknown
+
+info-id = ID
+info-created = Created
+info-none = None
+info-unknown = unknown
+info-more = { " " }and { $count } more
+info-bool = { $value ->
+    [true] Yes
+   *[false] No
+}
+userinfo-bot = Bot
+userinfo-nickname = Nickname
+userinfo-joined = Joined
+userinfo-join-position = Join position
+userinfo-join-position-value = #{ $position } of { $total }
+userinfo-roles = Roles ({ $count })
+serverinfo-owner = Owner
+serverinfo-members = Members
+serverinfo-channels = Channels
+serverinfo-roles = Roles
+serverinfo-boosts = Boosts
+roleinfo-color = Color
+roleinfo-position = Position
+roleinfo-members = Members
+roleinfo-hoisted = Shown separately
+roleinfo-mentionable = Mentionable
+roleinfo-managed = Managed by an integration
+roleinfo-permissions = Permissions
+channelinfo-type = Type
+channelinfo-kind = { $kind ->
+    [category] Category
+    [news] Announcements
+    [voice] Voice
+   *[text] Text
+}
+channelinfo-category = Category
+channelinfo-position = Position
+channelinfo-nsfw = NSFW
+channelinfo-slowmode = Slowmode
+channelinfo-seconds = { $seconds }s
+channelinfo-bitrate = Bitrate
+channelinfo-kbps = { $kbps } kbps
+channelinfo-user-limit = User limit
+avatar-title = Avatar of { $user }
+
+played-no-entries = No entries found for { $user }
+played-seen = *First seen { $first_seen }, last updated { $last_updated }*
+played-games = Games
+played-total = Total
+played-total-value = { $time } across { $games ->
+    [one] 1 game
+   *[other] { $games } games
+}
+played-page = Page { $page }/{ $pages }
+played-top-title = Most played games
+played-top-description = Across { $members ->
+    [one] 1 member
+   *[other] { $members } members
+}
+played-top-sampled = Across { $members } of { $total } members, picked at random
+played-top-game = { $total } in total. Top players: { $players }
+played-top-empty = Nobody here has played anything yet
+
+logs-enabled = Enabled message logs in { $channel }
+logs-disabled = Disabled message logs
+logs-not-enabled = Message logs aren't enabled
+logs-message-update = Message Update
+logs-message-deleted = Message Deleted
+logs-user = User
+logs-channel = Channel
+logs-old-content = Old content
+logs-new-content = New content
+logs-deleted-content = Deleted content
+logs-webhook-name = Message Logs
+logs-setting-changed = Setting Changed
+logs-setting = Setting
+logs-value = Value
+logs-moderation = { $action ->
+    [ban] Member Banned
+    [unban] Member Unbanned
+    [kick] Member Kicked
+    [mute] Member Muted
+    [unmute] Member Unmuted
+   *[warn] Member Warned
+}
+logs-moderator = Moderator
+logs-target = Member
+logs-reason = Reason
+
+settings-suggestions = { $enabled ->
+    [true] Command suggestions are on
+   *[false] Command suggestions are off
+}
+settings-language = This server's language is { $language }
+settings-language-unknown = Unknown language `{ $language }`. Available languages: { $languages }
+
+commands-list = Commands
+commands-state = { $enabled ->
+    [true] enabled
+   *[false] disabled
+}
+commands-enabled = Enabled `{ $name }`
+commands-disabled = Disabled `{ $name }`
+commands-unknown = No command called `{ $name }` is registered
+commands-protected = `{ $name }` can't be disabled
+
+control-presence-set = Updated the presence on { $shards ->
+    [one] 1 shard
+   *[other] { $shards } shards
+}
+control-rotating = Rotating presences again, now showing `{ $presence }`
+control-no-presences = No presences are configured to rotate through
+control-unknown-shard = There's no shard { $id }
+control-shard-restarted = Restarted shard { $id }
+control-reloaded = Reloaded the configuration and settings for { $guilds ->
+    [one] 1 server
+   *[other] { $guilds } servers
+}
+control-maintenance = { $enabled ->
+    [true] Maintenance mode is on, only owners can use commands
+   *[false] Maintenance mode is off
+}
+control-shutdown = Shutting down...
+
+audit-title = Recent commands
+audit-empty = No commands have been recorded yet
+audit-entry = { $when }: { $user } ran `{ $command }` - { $outcome ->
+    [success] ok
+    [bad-argument] bad argument
+    [denied] denied
+   *[error] error
+} ({ $ms }ms)
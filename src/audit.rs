@@ -0,0 +1,289 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use foundationdb::tuple;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use twilight_model::channel::Message;
+use twilight_model::id::{ChannelId, GuildId, MessageId, UserId};
+
+use crate::rikka::{Command, Rikka};
+use crate::scheduler::JobId;
+use crate::storage::{transact, Storage, StorageError, Transaction};
+
+/// How many days invocations are kept before they're cleared.
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+/// The kind of the job that clears expired invocations.
+pub(crate) const SWEEP_JOB: &str = "audit-sweep";
+pub(crate) const SWEEP_JOB_ID: JobId = 1;
+/// How often expired invocations are cleared.
+pub(crate) const SWEEP_INTERVAL_HOURS: i64 = 1;
+/// How many guilds and users are swept per transaction.
+const SWEEP_BATCH: usize = 100;
+
+/// A single command someone ran, and how it went.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Invocation {
+    pub id: MessageId,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub user_id: UserId,
+    /// The command and subcommands that were run, e.g. `log message enable`.
+    pub command: String,
+    pub args: String,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Success,
+    BadArgument,
+    /// The author wasn't allowed to run the command.
+    Denied,
+    Error,
+}
+
+impl Outcome {
+    /// The catalog selector for the outcome.
+    pub fn key(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::BadArgument => "bad-argument",
+            Outcome::Denied => "denied",
+            Outcome::Error => "error",
+        }
+    }
+}
+
+/// Works out which command path `msg` ran, and what arguments were left
+/// after it, by walking `cmd`'s subcommand tree.
+pub(crate) fn invocation_path(bot: &Rikka, cmd: &dyn Command, msg: &Message) -> (String, String) {
+    let parser = bot.parser.read().unwrap();
+    let (name, rest) = match parser.parse(&msg.content) {
+        Some(found) => (found.name.to_owned(), found.arguments.as_str().to_owned()),
+        None => return (cmd.name().to_owned(), String::new()),
+    };
+
+    let mut words = rest.split_whitespace().peekable();
+    let mut path = Vec::new();
+
+    match cmd.subcommands() {
+        Some(mut node) => {
+            path.push(node.name());
+            while let Some(child) = words.peek().and_then(|w| node.child(w)) {
+                path.push(child.name());
+                node = child;
+                words.next();
+            }
+        }
+        None => return (name, rest.trim().to_owned()),
+    }
+
+    (path.join(" "), words.collect::<Vec<_>>().join(" "))
+}
+
+/// Stores `inv` under its guild and user, and clears their entries older
+/// than `retention`.
+pub async fn record(storage: &dyn Storage, inv: &Invocation, retention: Duration) -> Result<()> {
+    #[inline]
+    async fn exec(
+        t: &dyn Transaction,
+        data: &(&Invocation, Vec<u8>, Duration),
+    ) -> Result<(), StorageError> {
+        let (inv, raw, retention) = data;
+        let cutoff = inv.at - *retention;
+
+        let mut indexes = vec![user_subspace(inv.user_id)];
+        if let Some(gid) = inv.guild_id {
+            indexes.push(guild_subspace(gid));
+        }
+
+        for sub in indexes {
+            t.set(&sub.pack(&(fmt_time(inv.at), inv.id.0)), raw);
+
+            // Keys sort newest first, so everything from the cutoff to the
+            // end of the subspace has expired.
+            let (_, end) = sub.range();
+            t.clear_range(&sub.pack(&fmt_time(cutoff)), &end);
+        }
+
+        Ok(())
+    }
+
+    let raw = serde_cbor::to_vec(inv)?;
+    transact(storage, (inv, raw, retention), |tx, data| {
+        exec(tx, data).boxed()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Clears invocations from before `cutoff` for every guild and user. Returns
+/// how many guilds and users were looked at.
+pub async fn sweep(storage: &dyn Storage, cutoff: DateTime<Utc>) -> Result<usize> {
+    #[inline]
+    async fn exec(
+        t: &dyn Transaction,
+        data: &(Vec<u8>, u64),
+    ) -> Result<(usize, Option<Vec<u8>>), StorageError> {
+        let (from, cutoff) = data;
+        let (_, end) = audit_subspace().range();
+        let mut begin = from.clone();
+
+        for swept in 0..SWEEP_BATCH {
+            let key = match t.range(&begin, &end, 1).await?.into_iter().next() {
+                Some((key, _)) => key,
+                None => return Ok((swept, None)),
+            };
+            let index = match audit_subspace().unpack::<(u16, u64, u64, u64)>(&key) {
+                Ok((kind, id, _, _)) => audit_subspace().subspace(&(kind, id)),
+                Err(_) => {
+                    begin = key;
+                    begin.push(0);
+                    continue;
+                }
+            };
+
+            let (_, index_end) = index.range();
+            t.clear_range(&index.pack(cutoff), &index_end);
+            begin = index_end;
+        }
+
+        Ok((SWEEP_BATCH, Some(begin)))
+    }
+
+    let (mut from, _) = audit_subspace().range();
+    let cutoff = fmt_time(cutoff);
+    let mut total = 0;
+    loop {
+        let (swept, next) =
+            transact(storage, (from, cutoff), |tx, data| exec(tx, data).boxed()).await?;
+        total += swept;
+
+        match next {
+            Some(next) => from = next,
+            None => return Ok(total),
+        }
+    }
+}
+
+/// Returns up to `limit` of the most recent invocations in `gid`.
+pub async fn by_guild(
+    storage: &dyn Storage,
+    gid: GuildId,
+    limit: usize,
+) -> Result<Vec<Invocation>> {
+    recent(storage, guild_subspace(gid), limit).await
+}
+
+/// Returns up to `limit` of the most recent invocations by `uid`, in any
+/// guild or DM.
+pub async fn by_user(storage: &dyn Storage, uid: UserId, limit: usize) -> Result<Vec<Invocation>> {
+    recent(storage, user_subspace(uid), limit).await
+}
+
+async fn recent(
+    storage: &dyn Storage,
+    sub: tuple::Subspace,
+    limit: usize,
+) -> Result<Vec<Invocation>> {
+    #[inline]
+    async fn exec(
+        t: &dyn Transaction,
+        data: &(tuple::Subspace, usize),
+    ) -> Result<Vec<Vec<u8>>, StorageError> {
+        let (begin, end) = data.0.range();
+        let kvs = t.range(&begin, &end, data.1).await?;
+
+        Ok(kvs.into_iter().map(|(_, v)| v).collect())
+    }
+
+    let raw = transact(storage, (sub, limit), |tx, data| exec(tx, data).boxed()).await?;
+
+    Ok(raw
+        .iter()
+        .filter_map(|v| serde_cbor::from_slice(v).ok())
+        .collect())
+}
+
+const SUBSPACE_PREFIX: &[u8] = b"audit";
+
+enum Subspace {
+    ByGuild = 1,
+    ByUser = 2,
+}
+
+fn audit_subspace() -> tuple::Subspace {
+    tuple::Subspace::all().subspace(&SUBSPACE_PREFIX)
+}
+
+fn guild_subspace(gid: GuildId) -> tuple::Subspace {
+    audit_subspace()
+        .subspace(&(Subspace::ByGuild as u16))
+        .subspace(&gid.0)
+}
+
+fn user_subspace(uid: UserId) -> tuple::Subspace {
+    audit_subspace()
+        .subspace(&(Subspace::ByUser as u16))
+        .subspace(&uid.0)
+}
+
+/// Inverts the timestamp so range reads return the newest entries first.
+fn fmt_time(at: DateTime<Utc>) -> u64 {
+    u64::MAX - at.timestamp_millis().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn invocation(id: u64, uid: u64, at: DateTime<Utc>) -> Invocation {
+        Invocation {
+            id: MessageId(id),
+            guild_id: Some(GuildId(100)),
+            channel_id: ChannelId(200),
+            user_id: UserId(uid),
+            command: "ping".to_owned(),
+            args: String::new(),
+            outcome: Outcome::Success,
+            duration_ms: 1,
+            at,
+        }
+    }
+
+    fn ids(invs: Vec<Invocation>) -> Vec<u64> {
+        invs.iter().map(|inv| inv.id.0).collect()
+    }
+
+    #[tokio::test]
+    async fn sweep_clears_expired_invocations() {
+        let storage = MemoryStorage::new();
+        let now = Utc::now();
+        let keep = Duration::days(365);
+        let old = now - Duration::days(40);
+        record(&storage, &invocation(1, 10, old), keep)
+            .await
+            .unwrap();
+        record(&storage, &invocation(2, 11, old), keep)
+            .await
+            .unwrap();
+        record(&storage, &invocation(3, 11, now), keep)
+            .await
+            .unwrap();
+
+        // One guild and two users.
+        let swept = sweep(&storage, now - Duration::days(30)).await.unwrap();
+        assert_eq!(swept, 3);
+
+        assert!(by_user(&storage, UserId(10), 10).await.unwrap().is_empty());
+        assert_eq!(ids(by_user(&storage, UserId(11), 10).await.unwrap()), [3]);
+        assert_eq!(
+            ids(by_guild(&storage, GuildId(100), 10).await.unwrap()),
+            [3]
+        );
+    }
+}
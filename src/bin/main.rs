@@ -1,8 +1,11 @@
 use anyhow::Result;
 use foundationdb::api::FdbApiBuilder;
 use std::env;
+use std::sync::Arc;
 
-use rikka_rs::{commands, Rikka};
+use rikka_rs::storage::{FdbStorage, Storage};
+use rikka_rs::{commands, Config, Rikka};
+use twilight_model::id::UserId;
 
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
@@ -22,7 +25,9 @@ async fn main() -> Result<()> {
     let fdb_network = cond.wait();
 
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
-    let mut r = Rikka::new(token).await?;
+    let storage: Arc<dyn Storage> = Arc::new(FdbStorage::new()?);
+    let mut r = Rikka::new(token, storage.clone()).await?;
+    r.register_owner(UserId(105484726235607040));
 
     if env::var("PROD").is_ok() {
         println!("prod mode");
@@ -30,13 +35,12 @@ async fn main() -> Result<()> {
     } else {
         r.register_prefix("rt.");
     }
-    // r.register_command(commands::general::Say {});
-    r.register_command(commands::general::Ping {});
-    r.register_command(commands::help::Help {});
-    r.register_command(commands::played::Played::new().await);
-    r.register_command(commands::logs::Logs::new().await?);
+    r.apply_config(&Config::load()?);
+    for cmd in commands::all(storage).await? {
+        r.register_shared_command(cmd);
+    }
 
-    let r = leak(r);
+    let r = Arc::new(r);
     println!("start");
     r.start().await?;
     println!("end");
@@ -45,7 +49,3 @@ async fn main() -> Result<()> {
     net_thread.join().expect("join fdb thread");
     Ok(())
 }
-
-fn leak<T>(cmd: T) -> &'static T {
-    Box::leak(Box::new(cmd))
-}
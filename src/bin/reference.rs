@@ -0,0 +1,28 @@
+//! Writes the command reference to `commands.md` and `commands.json`.
+//!
+//! Usage: `reference [output dir] [prefix]`, defaulting to `docs` and `r.`.
+//! Only the commands' help is read, so this doesn't need a database or any
+//! of the bot's environment.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rikka_rs::commands;
+use rikka_rs::reference::Reference;
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let out = PathBuf::from(args.next().unwrap_or_else(|| "docs".into()));
+    let prefix = args.next().unwrap_or_else(|| "r.".into());
+
+    let reference = Reference::new(&prefix, &commands::reference());
+
+    fs::create_dir_all(&out).context("create output dir")?;
+    fs::write(out.join("commands.md"), reference.to_markdown()).context("write markdown")?;
+    fs::write(out.join("commands.json"), reference.to_json()?).context("write json")?;
+    println!("wrote command reference to {}", out.display());
+
+    Ok(())
+}
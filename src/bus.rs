@@ -0,0 +1,74 @@
+use twilight_model::id::{GuildId, UserId};
+
+/// Something that happened inside the bot, as opposed to a gateway event.
+/// Published with [`crate::Rikka::publish`] and delivered to the
+/// [`crate::Command::receive_event`] of every enabled command that
+/// [subscribes](crate::Command::subscribes) to its kind.
+#[derive(Clone, Debug)]
+pub enum BotEvent {
+    SettingChanged(SettingChanged),
+    ModerationAction(ModerationAction),
+    CommandToggled { name: &'static str, enabled: bool },
+}
+
+/// Which variant a [`BotEvent`] is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotEventKind {
+    SettingChanged,
+    ModerationAction,
+    CommandToggled,
+}
+
+impl BotEvent {
+    pub fn kind(&self) -> BotEventKind {
+        match self {
+            BotEvent::SettingChanged(_) => BotEventKind::SettingChanged,
+            BotEvent::ModerationAction(_) => BotEventKind::ModerationAction,
+            BotEvent::CommandToggled { .. } => BotEventKind::CommandToggled,
+        }
+    }
+}
+
+/// A guild setting was changed through a command.
+#[derive(Clone, Debug)]
+pub struct SettingChanged {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub name: &'static str,
+    /// The new value as shown to users.
+    pub value: String,
+}
+
+/// A moderator acted against a member.
+#[derive(Clone, Debug)]
+pub struct ModerationAction {
+    pub guild_id: GuildId,
+    pub moderator_id: UserId,
+    pub target_id: UserId,
+    pub kind: ModerationKind,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModerationKind {
+    Ban,
+    Unban,
+    Kick,
+    Mute,
+    Unmute,
+    Warn,
+}
+
+impl ModerationKind {
+    /// The catalog selector for the action.
+    pub fn key(self) -> &'static str {
+        match self {
+            ModerationKind::Ban => "ban",
+            ModerationKind::Unban => "unban",
+            ModerationKind::Kick => "kick",
+            ModerationKind::Mute => "mute",
+            ModerationKind::Unmute => "unmute",
+            ModerationKind::Warn => "warn",
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::FutureExt;
+use twilight_model::channel::Message;
+use twilight_model::guild::Permissions;
+use twilight_model::id::UserId;
+
+use crate::audit::{self, Invocation};
+use crate::error::{ArgError, CommandResult};
+use crate::extract::{option_usage, usage, usage_of};
+use crate::format::truncate;
+use crate::help::{CommandHelp, HelpSection};
+use crate::i18n::Locale;
+use crate::parse::{matches_command, Args};
+use crate::permissions::Requirement;
+use crate::rikka::{Command, Rikka};
+use crate::subcommand::{Subcommand, SubcommandInfo};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 25;
+/// How many of a user's invocations to look through for ones in the current
+/// guild, for admins who can't see other guilds.
+const USER_SCAN_LIMIT: usize = 250;
+
+/// Looks up who ran which commands.
+pub struct Audit {
+    tree: Subcommand<Audit>,
+}
+
+impl Audit {
+    pub fn new() -> Audit {
+        Audit {
+            tree: Audit::tree(),
+        }
+    }
+
+    fn tree() -> Subcommand<Audit> {
+        Subcommand::new("audit")
+            .requires(Requirement::Guild)
+            .requires(Requirement::Permissions(Permissions::ADMINISTRATOR))
+            .child(
+                Subcommand::new("guild")
+                    .aliases(&["server"])
+                    .usage(option_usage::<usize>("limit"))
+                    .examples(&["audit guild", "audit guild --limit 25"])
+                    .handler(|_, bot, msg, args| Audit::handle_guild(bot, msg, args).boxed()),
+            )
+            .child(
+                Subcommand::new("user")
+                    .usage(usage_of(&[
+                        usage::<UserId>("user"),
+                        option_usage::<usize>("limit"),
+                    ]))
+                    .examples(&["audit user @user", "audit user 80351110224678912 -l 5"])
+                    .handler(|_, bot, msg, args| Audit::handle_user(bot, msg, args).boxed()),
+            )
+    }
+
+    async fn handle_guild<'a>(bot: &Rikka, msg: &Message, mut args: Args<'a>) -> CommandResult {
+        let gid = msg
+            .guild_id
+            .ok_or(Error::msg("message didn't have guild id"))?;
+        let limit = parse_limit(&mut args)?;
+
+        let invs = audit::by_guild(&*bot.storage, gid, limit).await?;
+        Ok(Some(fmt_invocations(bot, &bot.locale(msg.guild_id), &invs)))
+    }
+
+    async fn handle_user<'a>(bot: &Rikka, msg: &Message, mut args: Args<'a>) -> CommandResult {
+        let limit = parse_limit(&mut args)?;
+        let uid = args.parse::<UserId>("user")?;
+
+        // Owners can see everywhere the bot is, admins only their server.
+        let invs = if bot.is_owner(msg.author.id) {
+            audit::by_user(&*bot.storage, uid, limit).await?
+        } else {
+            audit::by_user(&*bot.storage, uid, USER_SCAN_LIMIT)
+                .await?
+                .into_iter()
+                .filter(|inv| inv.guild_id == msg.guild_id)
+                .take(limit)
+                .collect()
+        };
+
+        Ok(Some(fmt_invocations(bot, &bot.locale(msg.guild_id), &invs)))
+    }
+}
+
+fn parse_limit(args: &mut Args<'_>) -> Result<usize, ArgError> {
+    Ok(args
+        .option::<usize>("limit")?
+        .unwrap_or(DEFAULT_LIMIT)
+        .max(1)
+        .min(MAX_LIMIT))
+}
+
+fn fmt_invocations(bot: &Rikka, loc: &Locale, invs: &[Invocation]) -> String {
+    if invs.is_empty() {
+        return tr!(loc, "audit-empty");
+    }
+
+    let mut out = format!("**{}**", tr!(loc, "audit-title"));
+    for inv in invs {
+        let user = bot
+            .cache
+            .user(inv.user_id)
+            .map(|u| format!("{}#{}", u.name, u.discriminator))
+            .unwrap_or_else(|| inv.user_id.to_string());
+
+        let mut command = inv.command.clone();
+        if !inv.args.is_empty() {
+            command.push(' ');
+            command.push_str(&truncate(&inv.args, 100));
+        }
+
+        out.push_str(&format!(
+            "\n• {}",
+            tr!(
+                loc,
+                "audit-entry",
+                when = loc.relative(&inv.at),
+                user = user,
+                command = command.replace('`', "'"),
+                outcome = inv.outcome.key(),
+                ms = inv.duration_ms,
+            )
+        ));
+    }
+
+    out
+}
+
+const AUDIT_ALIAS: &[&'static str] = &["audit"];
+
+#[async_trait]
+impl Command for Audit {
+    fn name(&self) -> &'static str {
+        "audit"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "audit",
+            section: HelpSection::Moderation,
+            examples: &["audit guild", "audit user @user"],
+            ..Default::default()
+        }]
+    }
+
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        Some(&self.tree)
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let args = matches_command(bot, msg, AUDIT_ALIAS)?;
+        self.tree.dispatch(self, bot, msg, args).await
+    }
+}
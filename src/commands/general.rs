@@ -1,17 +1,26 @@
 use crate::error::CommandResult;
 use crate::parse::matches_command;
 use crate::rikka::Rikka;
-use anyhow::Context;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::Utc;
+use futures::future::{join_all, Future};
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use twilight_embed_builder::{EmbedBuilder, EmbedFieldBuilder};
 use twilight_model::channel::Message;
 
 use crate::help::CommandHelp;
+use crate::i18n::Locale;
 use crate::rikka::Command;
 
 pub struct Ping;
 
 const PING_ALIAS: &[&'static str] = &["ping"];
+/// How long `ping` waits for each dependency before giving up on it.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// The most shards listed individually; the rest are only counted.
+const SHARD_LINES: usize = 10;
 
 #[async_trait]
 impl Command for Ping {
@@ -22,29 +31,99 @@ impl Command for Ping {
     fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
         vec![CommandHelp {
             name: "ping",
+            examples: &["ping"],
             ..Default::default()
         }]
     }
 
     async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
         matches_command(bot, msg, PING_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
 
-        let start = Utc::now();
+        let start = Instant::now();
         let msg = bot
             .http
             .create_message(msg.channel_id)
-            .content("Pong!")
+            .content(tr!(loc, "ping-pong"))
             .context("add content")?
             .await
             .context("send message")?;
+        let rest = start.elapsed();
+
+        // Commands are asked in parallel so one slow dependency doesn't add
+        // to the others.
+        let cmds = bot.commands();
+        let (storage, probes) = futures::join!(
+            probe(storage_latency(bot)),
+            join_all(
+                cmds.iter()
+                    .filter_map(|cmd| cmd.latency(bot))
+                    .map(|(key, latency)| async move { (key, probe(latency).await) })
+            )
+        );
+
+        let mut embed = EmbedBuilder::new()
+            .title(tr!(loc, "ping-pong"))?
+            .field(
+                EmbedFieldBuilder::new(tr!(loc, "ping-rest"), fmt_latency(&loc, Some(&Ok(rest))))?
+                    .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    tr!(loc, "ping-storage"),
+                    fmt_latency(&loc, storage.as_ref()),
+                )?
+                .inline(),
+            );
+
+        for (key, latency) in probes {
+            embed = embed.field(
+                EmbedFieldBuilder::new(tr!(loc, key), fmt_latency(&loc, latency.as_ref()))?
+                    .inline(),
+            );
+        }
+
+        // Big bots have more shards than an embed has fields, so they share
+        // one, listing the slowest first.
+        let mut shards = bot
+            .cluster
+            .info()
+            .into_iter()
+            .map(|(id, info)| (id, info.latency().average()))
+            .collect::<Vec<_>>();
+        if !shards.is_empty() {
+            // Shards without acknowledged heartbeats come first.
+            shards.sort_by_key(|(id, avg)| (avg.is_some(), Reverse(*avg), *id));
+
+            let mut lines = shards
+                .iter()
+                .take(SHARD_LINES)
+                .map(|(id, avg)| {
+                    let latency = match avg {
+                        Some(avg) => fmt_latency(&loc, Some(&Ok(*avg))),
+                        None => tr!(loc, "ping-unknown"),
+                    };
+                    tr!(loc, "ping-shard", id = *id, latency = latency)
+                })
+                .collect::<Vec<_>>();
+            if shards.len() > SHARD_LINES {
+                lines.push(tr!(
+                    loc,
+                    "ping-shards-more",
+                    count = shards.len() - SHARD_LINES
+                ));
+            }
+
+            embed = embed.field(EmbedFieldBuilder::new(
+                tr!(loc, "ping-shards"),
+                lines.join("\n"),
+            )?);
+        }
 
         bot.http
             .update_message(msg.channel_id, msg.id)
-            .content(format!(
-                "Pong! - `{}ms`",
-                Utc::now().signed_duration_since(start).num_milliseconds()
-            ))
-            .context("set content")?
+            .embed(embed.build()?)
+            .context("set embed")?
             .await
             .context("update message")?;
 
@@ -52,27 +131,25 @@ impl Command for Ping {
     }
 }
 
-pub struct Say;
+/// How long a read from storage takes.
+async fn storage_latency(bot: &Rikka) -> Result<Duration> {
+    let start = Instant::now();
+    let tx = bot.storage.begin().await?;
+    tx.get(b"ping").await?;
 
-const SAY_ALIAS: &[&'static str] = &["say"];
-
-#[async_trait]
-impl Command for Say {
-    fn name(&self) -> &'static str {
-        "say"
-    }
-
-    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
-        let mut cmd = CommandHelp::default();
-        cmd.name = "say";
-        vec![cmd]
-    }
-
-    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
-        let args = matches_command(bot, msg, SAY_ALIAS)?;
+    Ok(start.elapsed())
+}
 
-        dbg!(msg);
+/// Waits up to [`PROBE_TIMEOUT`] for `latency`, returning `None` if it
+/// didn't finish in time.
+async fn probe(latency: impl Future<Output = Result<Duration>>) -> Option<Result<Duration>> {
+    timeout(PROBE_TIMEOUT, latency).await.ok()
+}
 
-        Ok(Some(format!("you said \"{}\"", args.as_str())))
+fn fmt_latency(loc: &Locale, latency: Option<&Result<Duration>>) -> String {
+    match latency {
+        Some(Ok(latency)) => tr!(loc, "ping-ms", ms = latency.as_millis() as u64),
+        Some(Err(err)) => tr!(loc, "ping-failed", error = err.to_string()),
+        None => tr!(loc, "ping-timed-out"),
     }
 }
@@ -1,13 +1,19 @@
 use crate::error::CommandResult;
-use crate::help::generate_help;
+use crate::extract::{flag_usage, usage, usage_of, Rest};
+use crate::help::{
+    find_command, generate_command_help, generate_help, generate_search, requirements, Listing,
+};
+use crate::i18n::Locale;
 use crate::parse::matches_command;
 use crate::rikka::Rikka;
 use anyhow::Context;
 use async_trait::async_trait;
-use twilight_model::channel::Message;
+use twilight_model::channel::{embed::Embed, Message};
 
 use crate::help::CommandHelp;
 use crate::rikka::Command;
+use crate::subcommand::fmt_unknown_option;
+use crate::suggest::closest;
 
 pub struct Help;
 
@@ -20,21 +26,114 @@ impl Command for Help {
     }
 
     fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
-        let mut cmd = CommandHelp::default();
-        cmd.name = "help";
-        vec![cmd]
+        vec![CommandHelp {
+            name: "help",
+            usage: usage_of(&[usage::<Option<String>>("command"), flag_usage("dm")]),
+            examples: &[
+                "help",
+                "help played",
+                "help log message enable",
+                "help search deleted messages",
+                "help --dm",
+            ],
+            ..Default::default()
+        }]
     }
 
     async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
-        matches_command(bot, msg, HELP_ALIAS)?;
-        let embed = generate_help(bot)?;
+        let mut args = matches_command(bot, msg, HELP_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+        let dm = args.flag("dm");
+        let listing = if dm {
+            Listing::Anywhere(msg)
+        } else {
+            Listing::Here(msg)
+        };
 
+        if let Some(name) = args.next() {
+            if name == "search" {
+                let query = args.parse::<Rest>("words")?;
+                return match generate_search(bot, &loc, listing, &query.0)? {
+                    Some(embed) => send_embed(bot, &loc, msg, embed, dm).await,
+                    None => Ok(Some(tr!(
+                        loc,
+                        "help-search-empty",
+                        query = query.0.as_ref()
+                    ))),
+                };
+            }
+
+            let (cmd, help) = match find_command(bot, &name) {
+                Some(found) => found,
+                None => {
+                    let names = bot.command_names();
+                    return Ok(Some(match closest(&name, names.iter().copied()) {
+                        Some(suggestion) => tr!(
+                            loc,
+                            "unknown-command-suggestion",
+                            name = name.as_ref(),
+                            suggestion = suggestion
+                        ),
+                        None => tr!(loc, "unknown-command", name = name.as_ref()),
+                    }));
+                }
+            };
+
+            let mut path = vec![help.name];
+            let mut node = cmd.subcommands();
+            let mut requires = requirements(&*cmd, &help);
+            for arg in args {
+                let parent = match node {
+                    Some(parent) => parent,
+                    None => break,
+                };
+                let child = match parent.child(&arg) {
+                    Some(child) => child,
+                    None => return Ok(Some(fmt_unknown_option(&loc, parent, &arg))),
+                };
+
+                path.push(child.name());
+                requires.extend_from_slice(child.requires());
+                node = Some(child);
+            }
+
+            let embed = generate_command_help(bot, &loc, &help, &path, node, &requires)?;
+            return send_embed(bot, &loc, msg, embed, dm).await;
+        }
+
+        let embed = generate_help(bot, &loc, Some(listing))?;
+        send_embed(bot, &loc, msg, embed, dm).await
+    }
+}
+
+/// Sends `embed` where `msg` was sent, or to its author's DMs if `dm` is
+/// set.
+async fn send_embed(
+    bot: &Rikka,
+    loc: &Locale<'_>,
+    msg: &Message,
+    embed: Embed,
+    dm: bool,
+) -> CommandResult {
+    if !dm || msg.guild_id.is_none() {
         bot.http
             .create_message(msg.channel_id)
             .embed(embed)
             .context("build embed")?
             .await
             .context("send help message")?;
-        Ok(None)
+        return Ok(None);
     }
+
+    let sent = async {
+        let channel = bot.http.create_private_channel(msg.author.id).await?;
+        bot.http.create_message(channel.id).embed(embed)?.await?;
+        Ok::<_, anyhow::Error>(())
+    };
+
+    // Most likely the user doesn't accept DMs from server members.
+    Ok(Some(match sent.await {
+        Ok(()) => tr!(loc, "help-sent-dm"),
+        Err(_) => tr!(loc, "help-dm-failed"),
+    }))
 }
@@ -0,0 +1,614 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use twilight_embed_builder::{image_source::ImageSource, EmbedBuilder, EmbedFieldBuilder};
+use twilight_model::channel::{embed::Embed, Channel, ChannelType, GuildChannel, Message};
+use twilight_model::guild::{Permissions, Role};
+use twilight_model::id::{ChannelId, GuildId, RoleId, UserId};
+use twilight_model::user::User;
+
+use crate::error::{CommandResult, Expected};
+use crate::extract::{invalid, usage};
+use crate::format::{fmt_date_relative, fmt_guild_icon, fmt_user_avatar, snowflake_created};
+use crate::help::{CommandHelp, HelpSection};
+use crate::i18n::Locale;
+use crate::parse::matches_command;
+use crate::permissions::{fmt_permissions, require_all, Requirement};
+use crate::rikka::{Command, Rikka};
+
+/// How many roles to list before summarizing the rest, so the field stays
+/// under Discord's length limit.
+const MAX_ROLES: usize = 30;
+
+/// Shows a user's account and, in a server, their membership.
+pub struct UserInfo;
+
+/// Shows the server the command is used in.
+pub struct ServerInfo;
+
+/// Shows a role in the current server.
+pub struct RoleInfo;
+
+/// Shows a channel in the current server.
+pub struct ChannelInfo;
+
+/// Shows a user's avatar in full size.
+pub struct Avatar;
+
+const USERINFO_ALIAS: &[&'static str] = &["userinfo", "whois"];
+const SERVERINFO_ALIAS: &[&'static str] = &["serverinfo", "guildinfo"];
+const ROLEINFO_ALIAS: &[&'static str] = &["roleinfo"];
+const CHANNELINFO_ALIAS: &[&'static str] = &["channelinfo"];
+const AVATAR_ALIAS: &[&'static str] = &["avatar", "av"];
+
+#[async_trait]
+impl Command for UserInfo {
+    fn name(&self) -> &'static str {
+        "userinfo"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "userinfo",
+            aliases: &["whois"],
+            section: HelpSection::Info,
+            usage: usage::<Option<UserId>>("user"),
+            examples: &["userinfo", "userinfo @user"],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let mut args = matches_command(bot, msg, USERINFO_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+
+        let raw = args.clone().next().unwrap_or_default();
+        let uid = args
+            .parse::<Option<UserId>>("user")?
+            .unwrap_or(msg.author.id);
+        let user = find_user(bot, uid)
+            .await?
+            .ok_or_else(|| invalid::<Option<UserId>>("user", Expected::User, &raw))?;
+
+        let mut embed = EmbedBuilder::new()
+            .title(format!("{}#{}", user.name, user.discriminator))?
+            .thumbnail(ImageSource::url(fmt_user_avatar(&user))?)
+            .field(field(tr!(loc, "info-id"), user.id.to_string())?)
+            .field(field(
+                tr!(loc, "info-created"),
+                fmt_created(&loc, user.id.0),
+            )?);
+        if user.bot {
+            embed = embed.field(field(tr!(loc, "userinfo-bot"), fmt_bool(&loc, true))?);
+        }
+
+        let member = match msg.guild_id {
+            Some(gid) => find_member(bot, gid, uid).await?,
+            None => None,
+        };
+        if let (Some(gid), Some(member)) = (msg.guild_id, member) {
+            if let Some(nick) = member.nick {
+                embed = embed.field(field(tr!(loc, "userinfo-nickname"), nick)?);
+            }
+            if let Some(joined) = member.joined_at.as_deref().and_then(parse_timestamp) {
+                embed = embed.field(field(
+                    tr!(loc, "userinfo-joined"),
+                    fmt_date_relative(&loc, &joined),
+                )?);
+            }
+            if let Some((position, total)) = join_position(bot, gid, uid) {
+                embed = embed.field(field(
+                    tr!(loc, "userinfo-join-position"),
+                    tr!(
+                        loc,
+                        "userinfo-join-position-value",
+                        position = loc.number(position as i64),
+                        total = loc.number(total as i64)
+                    ),
+                )?);
+            }
+            embed = embed.field(EmbedFieldBuilder::new(
+                tr!(loc, "userinfo-roles", count = member.roles.len()),
+                fmt_roles(bot, &loc, &member.roles),
+            )?);
+        }
+
+        send_embed(bot, msg, embed.build()?).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Command for ServerInfo {
+    fn name(&self) -> &'static str {
+        "serverinfo"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "serverinfo",
+            aliases: &["guildinfo"],
+            section: HelpSection::Info,
+            examples: &["serverinfo"],
+            requires: vec![Requirement::Guild],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        matches_command(bot, msg, SERVERINFO_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+        require_all(bot, msg, &[Requirement::Guild])?;
+
+        let gid = msg.guild_id.context("message didn't have guild id")?;
+        let guild = find_guild(bot, gid).await?.context("unknown guild")?;
+
+        let mut embed = EmbedBuilder::new()
+            .title(&guild.name)?
+            .field(field(tr!(loc, "info-id"), guild.id.to_string())?)
+            .field(field(
+                tr!(loc, "serverinfo-owner"),
+                format!("<@{}>", guild.owner_id),
+            )?)
+            .field(field(tr!(loc, "info-created"), fmt_created(&loc, gid.0))?)
+            .field(field(
+                tr!(loc, "serverinfo-members"),
+                guild
+                    .members
+                    .map(|n| loc.number(n as i64))
+                    .unwrap_or_else(|| tr!(loc, "info-unknown")),
+            )?)
+            .field(field(
+                tr!(loc, "serverinfo-channels"),
+                loc.number(guild.channels as i64),
+            )?)
+            .field(field(
+                tr!(loc, "serverinfo-roles"),
+                loc.number(guild.roles as i64),
+            )?)
+            .field(field(
+                tr!(loc, "serverinfo-boosts"),
+                loc.number(guild.boosts.unwrap_or(0) as i64),
+            )?);
+        if let Some(icon) = &guild.icon {
+            embed = embed.thumbnail(ImageSource::url(fmt_guild_icon(gid, icon))?);
+        }
+
+        send_embed(bot, msg, embed.build()?).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Command for RoleInfo {
+    fn name(&self) -> &'static str {
+        "roleinfo"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "roleinfo",
+            section: HelpSection::Info,
+            usage: usage::<RoleId>("role"),
+            examples: &["roleinfo @Moderators"],
+            requires: vec![Requirement::Guild],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let mut args = matches_command(bot, msg, ROLEINFO_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+        require_all(bot, msg, &[Requirement::Guild])?;
+
+        let gid = msg.guild_id.context("message didn't have guild id")?;
+        let raw = args.clone().next().unwrap_or_default();
+        let rid = args.parse::<RoleId>("role")?;
+        let role = find_role(bot, gid, rid)
+            .await?
+            .ok_or_else(|| invalid::<RoleId>("role", Expected::Role, &raw))?;
+
+        let members = bot.cache.guild_members(gid).map(|members| {
+            members
+                .into_iter()
+                .filter_map(|uid| bot.cache.member(gid, uid))
+                .filter(|member| member.roles.contains(&rid))
+                .count()
+        });
+        let permissions = if role.permissions.is_empty() {
+            tr!(loc, "info-none")
+        } else if role.permissions.contains(Permissions::ADMINISTRATOR) {
+            fmt_permissions(Permissions::ADMINISTRATOR)
+        } else {
+            fmt_permissions(role.permissions)
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title(&role.name)?
+            .field(field(tr!(loc, "info-id"), role.id.to_string())?)
+            .field(field(tr!(loc, "info-created"), fmt_created(&loc, rid.0))?)
+            .field(field(
+                tr!(loc, "roleinfo-color"),
+                format!("#{:06x}", role.color),
+            )?)
+            .field(field(
+                tr!(loc, "roleinfo-position"),
+                loc.number(role.position),
+            )?)
+            .field(field(
+                tr!(loc, "roleinfo-members"),
+                members
+                    .map(|n| loc.number(n as i64))
+                    .unwrap_or_else(|| tr!(loc, "info-unknown")),
+            )?)
+            .field(field(
+                tr!(loc, "roleinfo-hoisted"),
+                fmt_bool(&loc, role.hoist),
+            )?)
+            .field(field(
+                tr!(loc, "roleinfo-mentionable"),
+                fmt_bool(&loc, role.mentionable),
+            )?)
+            .field(field(
+                tr!(loc, "roleinfo-managed"),
+                fmt_bool(&loc, role.managed),
+            )?)
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "roleinfo-permissions"),
+                permissions,
+            )?);
+        if role.color != 0 {
+            embed = embed.color(role.color)?;
+        }
+
+        send_embed(bot, msg, embed.build()?).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Command for ChannelInfo {
+    fn name(&self) -> &'static str {
+        "channelinfo"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "channelinfo",
+            section: HelpSection::Info,
+            usage: usage::<Option<ChannelId>>("channel"),
+            examples: &["channelinfo", "channelinfo #general"],
+            requires: vec![Requirement::Guild],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let mut args = matches_command(bot, msg, CHANNELINFO_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+        require_all(bot, msg, &[Requirement::Guild])?;
+
+        let gid = msg.guild_id.context("message didn't have guild id")?;
+        let raw = args.clone().next().unwrap_or_default();
+        let cid = args
+            .parse::<Option<ChannelId>>("channel")?
+            .unwrap_or(msg.channel_id);
+        let chan = find_channel(bot, gid, cid)
+            .await?
+            .ok_or_else(|| invalid::<Option<ChannelId>>("channel", Expected::Channel, &raw))?;
+
+        let mut embed = EmbedBuilder::new()
+            .title(chan.name())?
+            .field(field(tr!(loc, "info-id"), cid.to_string())?)
+            .field(field(tr!(loc, "info-created"), fmt_created(&loc, cid.0))?)
+            .field(field(
+                tr!(loc, "channelinfo-type"),
+                tr!(loc, "channelinfo-kind", kind = channel_kind(&chan)),
+            )?);
+
+        match &*chan {
+            GuildChannel::Category(c) => {
+                embed = embed.field(field(
+                    tr!(loc, "channelinfo-position"),
+                    loc.number(c.position),
+                )?);
+            }
+            GuildChannel::Text(c) => {
+                embed = embed
+                    .field(field(
+                        tr!(loc, "channelinfo-category"),
+                        fmt_category(bot, &loc, c.parent_id),
+                    )?)
+                    .field(field(
+                        tr!(loc, "channelinfo-position"),
+                        loc.number(c.position),
+                    )?)
+                    .field(field(tr!(loc, "channelinfo-nsfw"), fmt_bool(&loc, c.nsfw))?)
+                    .field(field(
+                        tr!(loc, "channelinfo-slowmode"),
+                        match c.rate_limit_per_user {
+                            Some(secs) if secs > 0 => {
+                                tr!(loc, "channelinfo-seconds", seconds = secs)
+                            }
+                            _ => tr!(loc, "info-none"),
+                        },
+                    )?);
+                if let Some(topic) = c.topic.as_ref().filter(|t| !t.is_empty()) {
+                    embed = embed.description(topic)?;
+                }
+            }
+            GuildChannel::Voice(c) => {
+                embed = embed
+                    .field(field(
+                        tr!(loc, "channelinfo-category"),
+                        fmt_category(bot, &loc, c.parent_id),
+                    )?)
+                    .field(field(
+                        tr!(loc, "channelinfo-position"),
+                        loc.number(c.position),
+                    )?)
+                    .field(field(
+                        tr!(loc, "channelinfo-bitrate"),
+                        tr!(loc, "channelinfo-kbps", kbps = c.bitrate / 1000),
+                    )?)
+                    .field(field(
+                        tr!(loc, "channelinfo-user-limit"),
+                        match c.user_limit {
+                            Some(limit) if limit > 0 => loc.number(limit as i64),
+                            _ => tr!(loc, "info-none"),
+                        },
+                    )?);
+            }
+        }
+
+        send_embed(bot, msg, embed.build()?).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Command for Avatar {
+    fn name(&self) -> &'static str {
+        "avatar"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "avatar",
+            aliases: &["av"],
+            section: HelpSection::Info,
+            usage: usage::<Option<UserId>>("user"),
+            examples: &["avatar", "avatar @user"],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let mut args = matches_command(bot, msg, AVATAR_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+
+        let raw = args.clone().next().unwrap_or_default();
+        let uid = args
+            .parse::<Option<UserId>>("user")?
+            .unwrap_or(msg.author.id);
+        let user = find_user(bot, uid)
+            .await?
+            .ok_or_else(|| invalid::<Option<UserId>>("user", Expected::User, &raw))?;
+
+        let url = fmt_user_avatar(&user);
+        let embed = EmbedBuilder::new()
+            .title(tr!(
+                loc,
+                "avatar-title",
+                user = format!("{}#{}", user.name, user.discriminator)
+            ))?
+            .url(url.clone())
+            .image(ImageSource::url(format!("{}?size=1024", url))?)
+            .build()?;
+
+        send_embed(bot, msg, embed).await?;
+        Ok(None)
+    }
+}
+
+/// What the info commands need from a member, whether it came from the cache
+/// or the API.
+struct MemberInfo {
+    nick: Option<String>,
+    joined_at: Option<String>,
+    roles: Vec<RoleId>,
+}
+
+/// What `serverinfo` needs from a guild, whether it came from the cache or
+/// the API.
+struct GuildInfo {
+    id: GuildId,
+    name: String,
+    icon: Option<String>,
+    owner_id: UserId,
+    members: Option<u64>,
+    channels: usize,
+    roles: usize,
+    boosts: Option<u64>,
+}
+
+async fn find_user(bot: &Rikka, uid: UserId) -> Result<Option<Arc<User>>> {
+    if let Some(user) = bot.cache.user(uid) {
+        return Ok(Some(user));
+    }
+
+    let user = bot.http.user(uid).await.context("fetch user")?;
+    Ok(user.map(Arc::new))
+}
+
+async fn find_member(bot: &Rikka, gid: GuildId, uid: UserId) -> Result<Option<MemberInfo>> {
+    if let Some(member) = bot.cache.member(gid, uid) {
+        return Ok(Some(MemberInfo {
+            nick: member.nick.clone(),
+            joined_at: member.joined_at.clone(),
+            roles: member.roles.clone(),
+        }));
+    }
+
+    let member = bot
+        .http
+        .guild_member(gid, uid)
+        .await
+        .context("fetch member")?;
+    Ok(member.map(|member| MemberInfo {
+        nick: member.nick,
+        joined_at: member.joined_at,
+        roles: member.roles,
+    }))
+}
+
+async fn find_guild(bot: &Rikka, gid: GuildId) -> Result<Option<GuildInfo>> {
+    if let Some(guild) = bot.cache.guild(gid) {
+        return Ok(Some(GuildInfo {
+            id: guild.id,
+            name: guild.name.clone(),
+            icon: guild.icon.clone(),
+            owner_id: guild.owner_id,
+            members: guild.member_count,
+            channels: bot.cache.guild_channels(gid).map_or(0, |c| c.len()),
+            roles: bot.cache.guild_roles(gid).map_or(0, |r| r.len()),
+            boosts: guild.premium_subscription_count,
+        }));
+    }
+
+    let guild = bot.http.guild(gid).await.context("fetch guild")?;
+    Ok(guild.map(|guild| GuildInfo {
+        id: guild.id,
+        name: guild.name,
+        icon: guild.icon,
+        owner_id: guild.owner_id,
+        members: guild.member_count,
+        channels: guild.channels.len(),
+        roles: guild.roles.len(),
+        boosts: guild.premium_subscription_count,
+    }))
+}
+
+/// Finds a role, but only if it belongs to `gid`.
+async fn find_role(bot: &Rikka, gid: GuildId, rid: RoleId) -> Result<Option<Arc<Role>>> {
+    let in_guild = bot
+        .cache
+        .guild_roles(gid)
+        .map_or(false, |roles| roles.contains(&rid));
+    if let Some(role) = bot.cache.role(rid).filter(|_| in_guild) {
+        return Ok(Some(role));
+    }
+
+    let roles = bot.http.roles(gid).await.context("fetch roles")?;
+    Ok(roles.into_iter().find(|r| r.id == rid).map(Arc::new))
+}
+
+/// Finds a channel, but only if it belongs to `gid`.
+async fn find_channel(
+    bot: &Rikka,
+    gid: GuildId,
+    cid: ChannelId,
+) -> Result<Option<Arc<GuildChannel>>> {
+    if let Some(chan) = bot.cache.guild_channel(cid) {
+        return Ok(Some(chan).filter(|c| c.guild_id() == Some(gid)));
+    }
+
+    match bot.http.channel(cid).await.context("fetch channel")? {
+        Some(Channel::Guild(chan)) if chan.guild_id() == Some(gid) => Ok(Some(Arc::new(chan))),
+        _ => Ok(None),
+    }
+}
+
+/// Where `uid` is in the order the cached members of `gid` joined in, and
+/// how many members that's out of.
+fn join_position(bot: &Rikka, gid: GuildId, uid: UserId) -> Option<(usize, usize)> {
+    let mut joined = bot
+        .cache
+        .guild_members(gid)?
+        .into_iter()
+        .filter_map(|id| {
+            let member = bot.cache.member(gid, id)?;
+            let at = parse_timestamp(member.joined_at.as_deref()?)?;
+            Some((at, id))
+        })
+        .collect::<Vec<_>>();
+    joined.sort();
+
+    let idx = joined.iter().position(|(_, id)| *id == uid)?;
+    Some((idx + 1, joined.len()))
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Mentions each role, highest first.
+fn fmt_roles(bot: &Rikka, loc: &Locale, roles: &[RoleId]) -> String {
+    if roles.is_empty() {
+        return tr!(loc, "info-none");
+    }
+
+    let mut sorted = roles
+        .iter()
+        .map(|rid| (bot.cache.role(*rid).map_or(0, |r| r.position), *rid))
+        .collect::<Vec<_>>();
+    sorted.sort();
+    sorted.reverse();
+
+    let mut out = sorted
+        .iter()
+        .take(MAX_ROLES)
+        .map(|(_, rid)| format!("<@&{}>", rid))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if sorted.len() > MAX_ROLES {
+        out.push_str(&tr!(loc, "info-more", count = sorted.len() - MAX_ROLES));
+    }
+
+    out
+}
+
+fn channel_kind(chan: &GuildChannel) -> &'static str {
+    match chan {
+        GuildChannel::Category(_) => "category",
+        GuildChannel::Text(c) if c.kind == ChannelType::GuildNews => "news",
+        GuildChannel::Text(_) => "text",
+        GuildChannel::Voice(_) => "voice",
+    }
+}
+
+fn fmt_category(bot: &Rikka, loc: &Locale, parent: Option<ChannelId>) -> String {
+    match parent {
+        Some(cid) => bot
+            .cache
+            .guild_channel(cid)
+            .map(|c| c.name().to_owned())
+            .unwrap_or_else(|| cid.to_string()),
+        None => tr!(loc, "info-none"),
+    }
+}
+
+fn fmt_created(loc: &Locale, id: u64) -> String {
+    fmt_date_relative(loc, &snowflake_created(id))
+}
+
+fn fmt_bool(loc: &Locale, b: bool) -> String {
+    tr!(loc, "info-bool", value = b.to_string())
+}
+
+fn field(name: String, value: String) -> Result<EmbedFieldBuilder> {
+    Ok(EmbedFieldBuilder::new(name, value)?.inline())
+}
+
+async fn send_embed(bot: &Rikka, msg: &Message, embed: Embed) -> Result<()> {
+    bot.http
+        .create_message(msg.channel_id)
+        .embed(embed)
+        .context("build embed")?
+        .await
+        .context("send info")?;
+    Ok(())
+}
@@ -5,58 +5,97 @@ use anyhow::Result;
 use async_trait::async_trait;
 use byteorder::{ByteOrder, LittleEndian};
 use chrono::Utc;
-use foundationdb::Database;
-use foundationdb::{FdbResult, TransactOption};
 use futures::FutureExt;
 use rusoto_core::ByteStream;
-use rusoto_s3::{PutObjectRequest, S3Client, S3};
+use rusoto_s3::{HeadBucketRequest, PutObjectRequest, S3Client, S3};
 use rusoto_signature::region::Region;
 use std::sync::Arc;
+use std::time::Instant;
 use twilight_cache_inmemory::model::CachedGuild;
-use twilight_command_parser::Arguments;
 use twilight_embed_builder::{
     image_source::ImageSource, EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder,
 };
-use twilight_mention::ParseMention;
 use twilight_model::channel::{GuildChannel, Message};
 use twilight_model::gateway::{
     event::Event,
     payload::{MessageDelete, MessageUpdate},
 };
-use twilight_model::id::{AttachmentId, ChannelId, GuildId, MessageId};
-use twilight_model::user::User;
+use twilight_model::id::{AttachmentId, ChannelId, GuildId, MessageId, UserId};
 
 use super::adapter::Adapter;
 use super::encrypt::img_hash_secret;
+use crate::bus::{BotEvent, BotEventKind, ModerationAction, SettingChanged};
 use crate::error::{CommandError, CommandResult};
+use crate::extract::usage;
+use crate::format::{fmt_guild_icon, fmt_user_avatar};
 use crate::help::{CommandHelp, HelpSection};
-use crate::parse::matches_command;
-use crate::rikka::Command;
+use crate::i18n::Locale;
+use crate::parse::{matches_command, Args};
+use crate::permissions::Requirement;
 use crate::rikka::Rikka;
+use crate::rikka::{Command, LatencyProbe};
+use crate::storage::{transact, Storage, StorageError, Transaction};
+use crate::subcommand::{Subcommand, SubcommandInfo};
+use crate::webhooks::WebhookMessage;
 
 pub struct Logs {
-    fdb: Database,
+    storage: Arc<dyn Storage>,
     s3: S3Client,
+    tree: Subcommand<Logs>,
 
     pub nonce: String,
 }
 
 impl Logs {
-    pub async fn new() -> Result<Logs> {
-        let nonce = std::env::var("LOG_HASH_NONCE")?;
+    /// Creates the command with the nonce from `LOG_HASH_NONCE`.
+    pub async fn new(storage: Arc<dyn Storage>) -> Result<Logs> {
+        let nonce = std::env::var("LOG_HASH_NONCE").context("read LOG_HASH_NONCE")?;
+        Ok(Logs::with_nonce(storage, nonce))
+    }
 
-        let fdb = foundationdb::Database::default().expect("open fdb");
+    /// Creates the command with `nonce` instead of reading it from the
+    /// environment.
+    pub fn with_nonce(storage: Arc<dyn Storage>, nonce: String) -> Logs {
         let s3_region = Region::Custom {
             name: "b2-usw".into(),
             endpoint: "s3.us-west-000.backblazeb2.com".into(),
         };
         let s3 = S3Client::new(s3_region.clone());
 
-        Ok(Logs { fdb, s3, nonce })
+        Logs {
+            storage,
+            s3,
+            tree: Logs::tree(),
+            nonce,
+        }
+    }
+
+    fn tree() -> Subcommand<Logs> {
+        Subcommand::new("log")
+            .aliases(&["logs"])
+            .requires(Requirement::Guild)
+            .child(
+                Subcommand::new("message")
+                    .aliases(&["messages"])
+                    .requires(Requirement::Owner)
+                    .child(
+                        Subcommand::new("enable")
+                            .usage(usage::<Option<Arc<GuildChannel>>>("channel"))
+                            .examples(&["log message enable", "log message enable #logs"])
+                            .handler(|l, _, msg, args| {
+                                l.handle_messages_enable_command(msg, args).boxed()
+                            }),
+                    )
+                    .child(Subcommand::new("disable").handler(|l, bot, msg, _| {
+                        l.handle_messages_disable_command(bot, msg).boxed()
+                    })),
+            )
     }
 }
 
 const LOGS_ALIAS: &[&'static str] = &["logs", "log"];
+/// Where attachments of logged messages are uploaded.
+const ATTACHMENT_BUCKET: &str = "rikka-files";
 
 #[async_trait]
 impl Command for Logs {
@@ -69,21 +108,18 @@ impl Command for Logs {
             name: "log",
             aliases: &["logs"],
             section: HelpSection::Moderation,
+            examples: &["log message enable #logs", "log message disable"],
             ..Default::default()
         }]
     }
 
-    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
-        let mut args = matches_command(bot, msg, LOGS_ALIAS)?;
-        const LOG_TYPES: [&'static str; 2] = ["message", "help"];
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        Some(&self.tree)
+    }
 
-        Ok(match args.next() {
-            Some("message") | Some("messages") => {
-                self.handle_messages_command(bot, msg, args).await?
-            }
-            Some("help") | None => Some("help coming soon".to_owned()),
-            Some(_) => Some(format!("Unknown option. Expected one of {:?}", LOG_TYPES)),
-        })
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let args = matches_command(bot, msg, LOGS_ALIAS)?;
+        self.tree.dispatch(self, bot, msg, args).await
     }
 
     async fn receive_raw(&self, bot: &Rikka, ev: &Event) -> Result<(), CommandError> {
@@ -96,52 +132,83 @@ impl Command for Logs {
 
         Ok(())
     }
-}
 
-impl Logs {
-    async fn handle_messages_command<'a>(
-        &self,
-        bot: &Rikka,
-        msg: &Message,
-        mut args: Arguments<'a>,
-    ) -> CommandResult {
-        const LOG_OPTIONS: [&str; 2] = ["enable", "disable"];
+    fn subscribes(&self) -> &'static [BotEventKind] {
+        &[BotEventKind::SettingChanged, BotEventKind::ModerationAction]
+    }
 
-        if msg.author.id.0 != 105484726235607040 {
-            return Ok(Some(
-                "Only owners may use this command for now :)".to_owned(),
-            ));
+    async fn receive_event(&self, bot: &Rikka, ev: &BotEvent) -> Result<(), CommandError> {
+        match ev {
+            BotEvent::SettingChanged(c) => self.log_setting_changed(bot, c).await?,
+            BotEvent::ModerationAction(a) => self.log_moderation_action(bot, a).await?,
+            _ => {}
         }
 
-        Ok(match args.next() {
-            Some("enable") => self.handle_messages_enable_command(bot, msg, args).await?,
-            Some("disable") => None,
-            _ => Some(format!("Unknown option. Expected one of {:?}", LOG_OPTIONS)),
-        })
+        Ok(())
+    }
+
+    fn latency<'a>(&'a self, _: &'a Rikka) -> Option<(&'static str, LatencyProbe<'a>)> {
+        let probe = async move {
+            let start = Instant::now();
+            self.s3
+                .head_bucket(HeadBucketRequest {
+                    bucket: ATTACHMENT_BUCKET.to_string(),
+                    ..Default::default()
+                })
+                .await?;
+
+            Ok::<_, Error>(start.elapsed())
+        };
+
+        Some(("ping-object-storage", probe.boxed()))
     }
+
+    // Every message in a logged guild costs an FDB write and potentially
+    // several S3 uploads, so keep this lower than the default.
+    fn concurrency(&self) -> usize {
+        16
+    }
+}
+
+impl Logs {
     async fn handle_messages_enable_command<'a>(
         &self,
-        bot: &Rikka,
         msg: &Message,
-        mut args: Arguments<'a>,
+        mut args: Args<'a>,
     ) -> CommandResult {
-        let cid = match args.next().clone() {
-            Some(cid) => ChannelId::parse(cid)
-                .map_err(|e| Error::msg(format!("{}", e)))
-                .context("parse channel id")?,
-            None => msg.channel_id,
+        let ch = match args.parse::<Option<Arc<GuildChannel>>>("channel")? {
+            Some(ch) => ch,
+            None => args
+                .bot
+                .cache
+                .guild_channel(msg.channel_id)
+                .ok_or(anyhow!("channel not found in cache: {}", msg.channel_id))?,
         };
-        let ch = bot
-            .cache
-            .guild_channel(cid)
-            .ok_or(anyhow!("channel not found in cache: {}", cid))?;
         let gid = msg
             .guild_id
             .ok_or(Error::msg("message didn't have guild id"))?;
 
-        self.enable_messages(&gid, &cid).await?;
+        self.enable_messages(&gid, &ch.id()).await?;
+
+        Ok(Some(tr!(
+            args.bot.locale(msg.guild_id),
+            "logs-enabled",
+            channel = ch.name()
+        )))
+    }
+
+    async fn handle_messages_disable_command(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        let gid = msg
+            .guild_id
+            .ok_or(Error::msg("message didn't have guild id"))?;
+
+        if self.messages_enabled(&gid).await?.is_none() {
+            return Ok(Some(tr!(loc, "logs-not-enabled")));
+        }
+        self.disable_messages(&gid).await?;
 
-        Ok(Some(format!("Enabled message logs in {}", ch.name())))
+        Ok(Some(tr!(loc, "logs-disabled")))
     }
 
     async fn store_message(&self, _: &Rikka, msg: &Message) -> Result<()> {
@@ -158,7 +225,7 @@ impl Logs {
             let res = reqwest::get(&att.proxy_url).await?;
             self.s3
                 .put_object(PutObjectRequest {
-                    bucket: "rikka-files".to_string(),
+                    bucket: ATTACHMENT_BUCKET.to_string(),
                     key: fmt_attachment_key(&msg.id, &att.id),
                     content_type: res
                         .headers()
@@ -196,34 +263,91 @@ impl Logs {
         }
 
         let (chan, guild) = channel_and_guild(bot, cid)?;
+        let loc = bot.locale(Some(guild.id));
 
         let embed = EmbedBuilder::new()
-            .title("Message Update")?
+            .title(tr!(loc, "logs-message-update"))?
             .thumbnail(ImageSource::url(fmt_user_avatar(&msg.author))?)
             .timestamp(Utc::now().to_rfc3339())
-            .footer(
-                EmbedFooterBuilder::new(&guild.name)?
-                    .icon_url(ImageSource::url(fmt_guild_icon(&*guild))?),
-            )
+            .footer(guild_footer(&guild)?)
             .field(EmbedFieldBuilder::new(
-                "User",
+                tr!(loc, "logs-user"),
                 format!(
                     "<@{}> {}#{} {}",
                     msg.author.id, msg.author.name, msg.author.discriminator, msg.author.id
                 ),
             )?)
             .field(EmbedFieldBuilder::new(
-                "Channel",
+                tr!(loc, "logs-channel"),
                 format!("<#{}> {}", chan.id(), chan.id()),
             )?)
-            .field(EmbedFieldBuilder::new("Old content", &msg.content)?)
-            .field(EmbedFieldBuilder::new("New content", new_content)?);
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-old-content"),
+                &msg.content,
+            )?)
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-new-content"),
+                new_content,
+            )?);
 
-        bot.http.create_message(cid).embed(embed.build()?)?.await?;
+        let out = log_message(&loc, &guild).embed(embed.build()?);
+        bot.webhooks.send(&bot.http, cid, out).await?;
 
         Ok(())
     }
 
+    async fn log_setting_changed(&self, bot: &Rikka, change: &SettingChanged) -> Result<()> {
+        let cid = match self.messages_enabled(&change.guild_id).await? {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let (_, guild) = channel_and_guild(bot, cid)?;
+        let loc = bot.locale(Some(guild.id));
+
+        let embed = EmbedBuilder::new()
+            .title(tr!(loc, "logs-setting-changed"))?
+            .timestamp(Utc::now().to_rfc3339())
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-user"),
+                fmt_user_field(bot, change.user_id),
+            )?)
+            .field(EmbedFieldBuilder::new(tr!(loc, "logs-setting"), change.name)?.inline())
+            .field(EmbedFieldBuilder::new(tr!(loc, "logs-value"), &change.value)?.inline());
+
+        let out = log_message(&loc, &guild).embed(embed.build()?);
+        bot.webhooks.send(&bot.http, cid, out).await
+    }
+
+    async fn log_moderation_action(&self, bot: &Rikka, action: &ModerationAction) -> Result<()> {
+        let cid = match self.messages_enabled(&action.guild_id).await? {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let (_, guild) = channel_and_guild(bot, cid)?;
+        let loc = bot.locale(Some(guild.id));
+
+        let mut embed = EmbedBuilder::new()
+            .title(tr!(loc, "logs-moderation", action = action.kind.key()))?
+            .timestamp(Utc::now().to_rfc3339())
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-moderator"),
+                fmt_user_field(bot, action.moderator_id),
+            )?)
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-target"),
+                fmt_user_field(bot, action.target_id),
+            )?);
+
+        if let Some(reason) = &action.reason {
+            embed = embed.field(EmbedFieldBuilder::new(tr!(loc, "logs-reason"), reason)?);
+        }
+
+        let out = log_message(&loc, &guild).embed(embed.build()?);
+        bot.webhooks.send(&bot.http, cid, out).await
+    }
+
     async fn log_delete(&self, bot: &Rikka, msg_d: &MessageDelete) -> Result<()> {
         let cid = match self.messages_enabled(&msg_d.guild_id.unwrap()).await? {
             Some(id) => id,
@@ -235,59 +359,56 @@ impl Logs {
             None => return Ok(()),
         };
 
-        for a in &msg.attachments {
-            bot.http
-                .create_message(cid)
-                .content(fmt_attachment_url(&msg_d.id, &a.id))?
-                .await?;
-        }
-
         let (chan, guild) = channel_and_guild(bot, cid)?;
+        let loc = bot.locale(Some(guild.id));
 
         let mut embed = EmbedBuilder::new()
-            .title("Message Deleted")?
+            .title(tr!(loc, "logs-message-deleted"))?
             .thumbnail(ImageSource::url(fmt_user_avatar(&msg.author))?)
             .timestamp(Utc::now().to_rfc3339())
-            .footer(
-                EmbedFooterBuilder::new(&guild.name)?
-                    .icon_url(ImageSource::url(fmt_guild_icon(&*guild))?),
-            )
+            .footer(guild_footer(&guild)?)
             .field(EmbedFieldBuilder::new(
-                "User",
+                tr!(loc, "logs-user"),
                 format!(
                     "<@{}> {}#{} {}",
                     msg.author.id, msg.author.name, msg.author.discriminator, msg.author.id
                 ),
             )?)
             .field(EmbedFieldBuilder::new(
-                "Channel",
+                tr!(loc, "logs-channel"),
                 format!("<#{}> {}", chan.id(), chan.id()),
             )?);
 
         if !msg.content.is_empty() {
-            embed = embed.field(EmbedFieldBuilder::new("Deleted content", msg.content)?);
+            embed = embed.field(EmbedFieldBuilder::new(
+                tr!(loc, "logs-deleted-content"),
+                msg.content,
+            )?);
         }
 
-        bot.http.create_message(cid).embed(embed.build()?)?.await?;
+        let mut out = log_message(&loc, &guild).embed(embed.build()?);
+        if !msg.attachments.is_empty() {
+            let urls = msg
+                .attachments
+                .iter()
+                .map(|a| fmt_attachment_url(&msg_d.id, &a.id))
+                .collect::<Vec<_>>();
+            out = out.content(urls.join("\n"));
+        }
+        bot.webhooks.send(&bot.http, cid, out).await?;
 
         Ok(())
     }
 
     async fn write_msg(&self, msg: &Message) -> Result<()> {
         #[inline]
-        async fn exec(t: &foundationdb::Transaction, msg: &Message) -> FdbResult<()> {
+        async fn exec(t: &dyn Transaction, msg: &Message) -> Result<(), StorageError> {
             let msg_raw = serde_cbor::to_vec(msg).unwrap();
             t.set(fmt_msg_key(&msg.id).as_slice(), &msg_raw);
             Ok(())
         }
 
-        self.fdb
-            .transact_boxed(
-                msg,
-                |tx, msg| exec(tx, msg).boxed(),
-                TransactOption::default(),
-            )
-            .await?;
+        transact(&*self.storage, msg, |tx, msg| exec(tx, msg).boxed()).await?;
 
         Ok(())
     }
@@ -295,9 +416,9 @@ impl Logs {
     async fn enable_messages(&self, gid: &GuildId, cid: &ChannelId) -> Result<()> {
         #[inline]
         async fn exec(
-            t: &foundationdb::Transaction,
+            t: &dyn Transaction,
             ids: &(&GuildId, &ChannelId),
-        ) -> FdbResult<()> {
+        ) -> Result<(), StorageError> {
             let (gid, cid) = ids;
             let mut cid_raw = [0u8; 8];
             LittleEndian::write_u64(&mut cid_raw, cid.0);
@@ -306,13 +427,19 @@ impl Logs {
             Ok(())
         }
 
-        self.fdb
-            .transact_boxed(
-                (gid, cid),
-                |tx, ids| exec(tx, ids).boxed(),
-                TransactOption::default(),
-            )
-            .await?;
+        transact(&*self.storage, (gid, cid), |tx, ids| exec(tx, ids).boxed()).await?;
+
+        Ok(())
+    }
+
+    async fn disable_messages(&self, gid: &GuildId) -> Result<()> {
+        #[inline]
+        async fn exec(t: &dyn Transaction, gid: &GuildId) -> Result<(), StorageError> {
+            t.clear(&fmt_messages_enabled_key(gid));
+            Ok(())
+        }
+
+        transact(&*self.storage, gid, |tx, gid| exec(tx, gid).boxed()).await?;
 
         Ok(())
     }
@@ -320,24 +447,17 @@ impl Logs {
     async fn messages_enabled(&self, gid: &GuildId) -> Result<Option<ChannelId>> {
         #[inline]
         async fn exec(
-            t: &foundationdb::Transaction,
+            t: &dyn Transaction,
             gid: &GuildId,
-        ) -> FdbResult<Option<ChannelId>> {
-            let ch = t.get(&fmt_messages_enabled_key(gid), true).await?;
+        ) -> Result<Option<ChannelId>, StorageError> {
+            let ch = t.get(&fmt_messages_enabled_key(gid)).await?;
             match ch {
                 Some(cid) => Ok(Some(LittleEndian::read_u64(&cid).into())),
                 None => Ok(None),
             }
         }
 
-        let ch = self
-            .fdb
-            .transact_boxed(
-                gid,
-                |tx, gid| exec(tx, gid).boxed(),
-                TransactOption::default(),
-            )
-            .await?;
+        let ch = transact(&*self.storage, gid, |tx, gid| exec(tx, gid).boxed()).await?;
 
         Ok(ch)
     }
@@ -345,26 +465,46 @@ impl Logs {
     async fn get_message(&self, mid: &MessageId) -> Result<Option<Message>> {
         #[inline]
         async fn exec(
-            t: &foundationdb::Transaction,
+            t: &dyn Transaction,
             mid: &MessageId,
-        ) -> FdbResult<Option<Message>> {
-            let msg = t.get(&fmt_msg_key(mid), true).await?;
+        ) -> Result<Option<Message>, StorageError> {
+            let msg = t.get(&fmt_msg_key(mid)).await?;
             match msg {
                 Some(msg) => Ok(serde_cbor::from_slice::<Message>(&msg).unwrap().into()),
                 None => Ok(None),
             }
         }
 
-        let ch = self
-            .fdb
-            .transact_boxed(
-                mid,
-                |tx, mid| exec(tx, mid).boxed(),
-                TransactOption::default(),
-            )
-            .await?;
+        let msg = transact(&*self.storage, mid, |tx, mid| exec(tx, mid).boxed()).await?;
 
-        Ok(ch)
+        Ok(msg)
+    }
+}
+
+/// A log message posted as the guild, so logs stand out from the bot's
+/// regular replies.
+fn log_message(loc: &Locale, guild: &CachedGuild) -> WebhookMessage {
+    let out = WebhookMessage::new().username(tr!(loc, "logs-webhook-name"));
+    match &guild.icon {
+        Some(icon) => out.avatar_url(fmt_guild_icon(guild.id, icon)),
+        None => out,
+    }
+}
+
+/// A footer with the guild's name, and its icon if it has one.
+fn guild_footer(guild: &CachedGuild) -> Result<EmbedFooterBuilder> {
+    let footer = EmbedFooterBuilder::new(&guild.name)?;
+    Ok(match &guild.icon {
+        Some(icon) => footer.icon_url(ImageSource::url(fmt_guild_icon(guild.id, icon))?),
+        None => footer,
+    })
+}
+
+/// Mentions a user, with their tag if they're cached.
+fn fmt_user_field(bot: &Rikka, uid: UserId) -> String {
+    match bot.cache.user(uid) {
+        Some(user) => format!("<@{}> {}#{} {}", uid, user.name, user.discriminator, uid),
+        None => format!("<@{}> {}", uid, uid),
     }
 }
 
@@ -419,20 +559,3 @@ fn fmt_attachment_url(mid: &MessageId, aid: &AttachmentId) -> String {
         img_hash_secret(&mid, &aid)
     )
 }
-
-fn fmt_guild_icon(g: &CachedGuild) -> String {
-    let icon = g.icon.as_ref().map(|i| i.to_owned()).unwrap_or("".into());
-    let ext = if icon.starts_with("a_") { "gif" } else { "png" };
-    format!("https://cdn.discordapp.com/icons/{}/{}.{}", g.id, icon, ext)
-}
-
-fn fmt_user_avatar(usr: &User) -> String {
-    let av = usr
-        .avatar
-        .as_ref()
-        .map(|av| format!("{}/{}", usr.id, av))
-        .unwrap_or((usr.discriminator.parse::<u16>().unwrap() % 5).to_string());
-    let ext = if av.starts_with("a_") { "gif" } else { "png" };
-
-    format!("https://cdn.discordapp.com/avatars/{}.{}", av, ext)
-}
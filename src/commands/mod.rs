@@ -1,4 +1,51 @@
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::rikka::Command;
+use crate::storage::{MemoryStorage, Storage};
+
+pub mod audit;
 pub mod general;
 pub mod help;
+pub mod info;
 pub mod logs;
+pub mod owner;
 pub mod played;
+pub mod say;
+pub mod settings;
+pub mod stats;
+
+/// Every command the bot runs with, in the order they're registered.
+pub async fn all(storage: Arc<dyn Storage>) -> Result<Vec<Arc<dyn Command>>> {
+    Ok(with_logs(logs::Logs::new(storage).await?))
+}
+
+/// Every command, created without connecting to anything so their help can
+/// be read. Storage is a throwaway [`MemoryStorage`], so they shouldn't be
+/// registered with a running bot.
+pub fn reference() -> Vec<Arc<dyn Command>> {
+    with_logs(logs::Logs::with_nonce(
+        Arc::new(MemoryStorage::new()),
+        String::new(),
+    ))
+}
+
+fn with_logs(logs: logs::Logs) -> Vec<Arc<dyn Command>> {
+    vec![
+        Arc::new(general::Ping {}),
+        Arc::new(help::Help {}),
+        Arc::new(played::Played::new()),
+        Arc::new(logs),
+        Arc::new(settings::Settings::new()),
+        Arc::new(owner::Commands::new()),
+        Arc::new(owner::Control::new()),
+        Arc::new(audit::Audit::new()),
+        Arc::new(say::Say {}),
+        Arc::new(stats::Stats {}),
+        Arc::new(info::UserInfo {}),
+        Arc::new(info::ServerInfo {}),
+        Arc::new(info::RoleInfo {}),
+        Arc::new(info::ChannelInfo {}),
+        Arc::new(info::Avatar {}),
+    ]
+}
@@ -0,0 +1,286 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::FutureExt;
+use twilight_model::channel::Message;
+
+use crate::bus::BotEvent;
+use crate::error::CommandResult;
+use crate::extract::{option_usage, usage, usage_of, Choice, Rest};
+use crate::help::{CommandHelp, HelpSection};
+use crate::parse::{matches_command, Args};
+use crate::permissions::Requirement;
+use crate::presence::{self, activity, set_presence, ActivityKind, PresenceStatus};
+use crate::rikka::{Command, Rikka};
+use crate::settings::Toggle;
+use crate::subcommand::{Subcommand, SubcommandInfo};
+
+/// Lists, enables and disables registered commands while the bot is running.
+pub struct Commands {
+    tree: Subcommand<Commands>,
+}
+
+impl Commands {
+    pub fn new() -> Commands {
+        Commands {
+            tree: Commands::tree(),
+        }
+    }
+
+    fn tree() -> Subcommand<Commands> {
+        Subcommand::new("commands")
+            .aliases(&["cmds"])
+            .requires(Requirement::Owner)
+            .child(
+                Subcommand::new("list")
+                    .handler(|_, bot, msg, _| Commands::handle_list(bot, msg).boxed()),
+            )
+            .child(
+                Subcommand::new("enable")
+                    .usage(usage::<String>("command"))
+                    .examples(&["commands enable played"])
+                    .handler(|_, bot, msg, args| {
+                        Commands::handle_set(bot, msg, args, true).boxed()
+                    }),
+            )
+            .child(
+                Subcommand::new("disable")
+                    .usage(usage::<String>("command"))
+                    .examples(&["commands disable played"])
+                    .handler(|_, bot, msg, args| {
+                        Commands::handle_set(bot, msg, args, false).boxed()
+                    }),
+            )
+    }
+
+    async fn handle_list(bot: &Rikka, msg: &Message) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+
+        let mut out = format!("**{}**", tr!(loc, "commands-list"));
+        for (name, enabled) in bot.command_states() {
+            out.push_str(&format!(
+                "\n• `{}` - {}",
+                name,
+                tr!(loc, "commands-state", enabled = enabled.to_string())
+            ));
+        }
+
+        Ok(Some(out))
+    }
+
+    async fn handle_set<'a>(
+        bot: &Rikka,
+        msg: &Message,
+        mut args: Args<'a>,
+        enabled: bool,
+    ) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        let name = args.parse::<String>("command")?;
+
+        // Disabling this command would leave no way to turn it back on.
+        if !enabled && COMMANDS_ALIAS.contains(&name.as_str()) {
+            return Ok(Some(tr!(loc, "commands-protected", name = name)));
+        }
+
+        let name = match bot.set_command_enabled(&name, enabled) {
+            Some(name) => name,
+            None => return Ok(Some(tr!(loc, "commands-unknown", name = name))),
+        };
+        bot.publish(BotEvent::CommandToggled { name, enabled });
+
+        Ok(Some(if enabled {
+            tr!(loc, "commands-enabled", name = name)
+        } else {
+            tr!(loc, "commands-disabled", name = name)
+        }))
+    }
+}
+
+const COMMANDS_ALIAS: &[&'static str] = &["commands", "cmds"];
+
+#[async_trait]
+impl Command for Commands {
+    fn name(&self) -> &'static str {
+        "commands"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "commands",
+            aliases: &["cmds"],
+            section: HelpSection::Owner,
+            examples: &["commands list", "commands disable played"],
+            ..Default::default()
+        }]
+    }
+
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        Some(&self.tree)
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let args = matches_command(bot, msg, COMMANDS_ALIAS)?;
+        self.tree.dispatch(self, bot, msg, args).await
+    }
+}
+
+/// Operates the running bot: its presence, shards, settings and lifecycle.
+pub struct Control {
+    tree: Subcommand<Control>,
+}
+
+impl Control {
+    pub fn new() -> Control {
+        Control {
+            tree: Control::tree(),
+        }
+    }
+
+    fn tree() -> Subcommand<Control> {
+        Subcommand::new("bot")
+            .requires(Requirement::Owner)
+            .child(
+                Subcommand::new("presence")
+                    .aliases(&["status"])
+                    .usage(usage_of(&[
+                        usage::<Choice<PresenceStatus>>("status"),
+                        usage::<Option<Rest>>("text"),
+                        option_usage::<Choice<ActivityKind>>("type"),
+                    ]))
+                    .examples(&[
+                        "bot presence dnd",
+                        "bot presence online r.help",
+                        "bot presence idle the logs --type watching",
+                    ])
+                    .handler(|_, bot, msg, args| Control::handle_presence(bot, msg, args).boxed()),
+            )
+            .child(
+                Subcommand::new("rotate")
+                    .handler(|_, bot, msg, _| Control::handle_rotate(bot, msg).boxed()),
+            )
+            .child(
+                Subcommand::new("restart")
+                    .usage(usage::<u64>("shard"))
+                    .examples(&["bot restart 0"])
+                    .handler(|_, bot, msg, args| Control::handle_restart(bot, msg, args).boxed()),
+            )
+            .child(
+                Subcommand::new("reload")
+                    .handler(|_, bot, msg, _| Control::handle_reload(bot, msg).boxed()),
+            )
+            .child(
+                Subcommand::new("maintenance")
+                    .usage(usage::<Option<Choice<Toggle>>>("state"))
+                    .examples(&["bot maintenance", "bot maintenance off"])
+                    .handler(|_, bot, msg, args| {
+                        Control::handle_maintenance(bot, msg, args).boxed()
+                    }),
+            )
+            .child(
+                Subcommand::new("shutdown")
+                    .handler(|_, bot, msg, _| Control::handle_shutdown(bot, msg).boxed()),
+            )
+    }
+
+    async fn handle_presence<'a>(bot: &Rikka, msg: &Message, mut args: Args<'a>) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        let kind = args
+            .option::<Choice<ActivityKind>>("type")?
+            .map_or(ActivityKind::Playing, |Choice(kind)| kind);
+        let Choice(status) = args.parse::<Choice<PresenceStatus>>("status")?;
+        let text = args.parse::<Option<Rest>>("text")?;
+
+        let activity = text.map(|Rest(text)| activity(kind, text.into_owned()));
+        bot.presences.pause();
+        let shards = set_presence(bot, status, activity).await;
+
+        Ok(Some(tr!(loc, "control-presence-set", shards = shards)))
+    }
+
+    async fn handle_rotate(bot: &Rikka, msg: &Message) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+
+        bot.presences.resume();
+        Ok(Some(match presence::apply(bot).await {
+            Some(shown) => tr!(loc, "control-rotating", presence = shown),
+            None => tr!(loc, "control-no-presences"),
+        }))
+    }
+
+    async fn handle_restart<'a>(bot: &Rikka, msg: &Message, mut args: Args<'a>) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        let id = args.parse::<u64>("shard")?;
+
+        let mut shard = match bot.cluster.shard(id) {
+            Some(shard) => shard,
+            None => return Ok(Some(tr!(loc, "control-unknown-shard", id = id))),
+        };
+        shard.shutdown();
+        shard.start().await.context("start shard")?;
+        // Shutting down drops the shard's listeners, which takes it out of
+        // the cluster's event stream, so listen to it again.
+        bot.forward_events(id, shard.events());
+
+        Ok(Some(tr!(loc, "control-shard-restarted", id = id)))
+    }
+
+    async fn handle_reload(bot: &Rikka, msg: &Message) -> CommandResult {
+        let guilds = bot.reload().await?;
+        Ok(Some(tr!(
+            bot.locale(msg.guild_id),
+            "control-reloaded",
+            guilds = guilds
+        )))
+    }
+
+    async fn handle_maintenance<'a>(
+        bot: &Rikka,
+        msg: &Message,
+        mut args: Args<'a>,
+    ) -> CommandResult {
+        let on = match args.parse::<Option<Choice<Toggle>>>("state")? {
+            Some(Choice(state)) => state.enabled(),
+            None => !bot.in_maintenance(),
+        };
+        bot.set_maintenance(on);
+
+        Ok(Some(tr!(
+            bot.locale(msg.guild_id),
+            "control-maintenance",
+            enabled = on.to_string()
+        )))
+    }
+
+    async fn handle_shutdown(bot: &Rikka, msg: &Message) -> CommandResult {
+        // The reply is still sent, since shutting down waits for running
+        // handlers.
+        bot.shutdown();
+        Ok(Some(tr!(bot.locale(msg.guild_id), "control-shutdown")))
+    }
+}
+
+const CONTROL_ALIAS: &[&'static str] = &["bot"];
+
+#[async_trait]
+impl Command for Control {
+    fn name(&self) -> &'static str {
+        "bot"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "bot",
+            section: HelpSection::Owner,
+            examples: &["bot maintenance on", "bot restart 0", "bot shutdown"],
+            ..Default::default()
+        }]
+    }
+
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        Some(&self.tree)
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let args = matches_command(bot, msg, CONTROL_ALIAS)?;
+        self.tree.dispatch(self, bot, msg, args).await
+    }
+}
@@ -1,28 +1,60 @@
-use crate::error::CommandResult;
+use crate::error::{ArgError, CommandResult, Expected};
+use crate::extract::{option_usage, usage, usage_of, Choice};
+use crate::format::fmt_user_avatar;
+use crate::limits::KeyedLocks;
 use crate::parse::matches_command;
+use crate::permissions::{require_all, Requirement};
 use crate::rikka::Rikka;
+use crate::storage::{transact, Storage, StorageError, Transaction};
+use anyhow::Context;
 use anyhow::Result;
-use anyhow::{Context, Error};
 use async_trait::async_trait;
+use cached::{Cached, TimedCache};
 use chrono::Duration;
-use chrono::Utc;
-use rs_humanize::time;
-use twilight_embed_builder::{image_source::ImageSource, EmbedBuilder, EmbedFieldBuilder};
-use twilight_mention::ParseMention;
-use twilight_model::channel::embed::Embed;
+use chrono::{DateTime, Utc};
+use foundationdb::tuple;
+use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use strum_macros::{EnumString, EnumVariantNames};
+use twilight_embed_builder::{
+    image_source::ImageSource, EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder,
+};
 use twilight_model::channel::Message;
-use twilight_model::id::UserId;
-use twilight_model::user::User;
+use twilight_model::id::{GuildId, UserId};
 
 use crate::help::{CommandHelp, HelpSection};
+use crate::i18n::Locale;
 use crate::rikka::Command;
 use played_rs::Runner;
 
 pub struct Played {
-    c: &'static Runner,
+    source: Box<dyn PlayedSource>,
+    /// Guild leaderboards, which take a read per member to build.
+    top: Mutex<TimedCache<GuildId, Arc<Leaderboard>>>,
+    /// Held while a guild's leaderboard is built so that concurrent calls wait
+    /// for it instead of each reading every member.
+    building: KeyedLocks<GuildId>,
 }
 
 const PLAYED_ALIAS: &[&'static str] = &["played"];
+/// The most games listed on one page.
+const PAGE_SIZE: usize = 15;
+/// How long an embed field's value may be.
+const FIELD_LIMIT: usize = 1024;
+/// How many members' entries are read at once when building a leaderboard.
+const TOP_CONCURRENCY: usize = 16;
+/// The most members read for a leaderboard. Larger guilds are sampled.
+const TOP_MAX_MEMBERS: usize = 1000;
+/// How long a leaderboard is reused before it's built again.
+const TOP_CACHE_SECS: u64 = 5 * 60;
+/// How many games a leaderboard shows.
+const TOP_GAMES: usize = 10;
+/// How many players are shown for each game on a leaderboard.
+const TOP_PLAYERS: usize = 3;
 
 #[async_trait]
 impl Command for Played {
@@ -34,86 +66,118 @@ impl Command for Played {
         vec![CommandHelp {
             name: "played",
             section: HelpSection::Fun,
+            usage: usage_of(&[
+                usage::<Option<UserId>>("user"),
+                option_usage::<Choice<PlayedSort>>("sort"),
+                option_usage::<usize>("limit"),
+                option_usage::<usize>("page"),
+            ]),
+            examples: &[
+                "played",
+                "played @user",
+                "played --sort name --page 2",
+                "played --sort recent",
+                "played @user --limit 5",
+                "played top",
+            ],
             ..Default::default()
         }]
     }
 
     async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
         let mut args = matches_command(bot, msg, PLAYED_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
 
-        let uid = match args.next() {
-            Some(uid) => {
-                let parsed =
-                    UserId::parse(uid).map_err(|e| Error::msg(format!("parse mention: {}", e)));
-
-                match parsed {
-                    Ok(uid) => uid,
-                    Err(err) => UserId(uid.parse().map_err(|e| {
-                        err.context(format!("parse id: {}", e)).context(format!(
-                            "no parsing options left, expected mention or id. invalid option '{}'",
-                            uid
-                        ))
-                    })?),
-                }
-            }
-            None => msg.author.id,
-        };
-
-        let entries = self
-            .c
-            .read(uid.0.to_string())
-            .await
-            .context("read played entries")?;
-        let user = bot.cache.user(uid).context("unknown user")?;
-
-        if entries.games.len() == 0 {
-            return Ok(Some(format!(
-                "No entries found for {}#{}",
-                &user.name, &user.discriminator
-            )));
+        if args.clone().next().as_deref() == Some("top") {
+            return self.receive_top(bot, msg).await;
         }
 
-        fn embed(user: &User, res: played_rs::Response) -> Result<Embed> {
-            let mut games_str = String::new();
-            for entry in &res.games {
-                let dur = Duration::seconds(entry.dur as i64);
-                games_str.push_str(&format!("• **{}** ", &entry.name));
+        let sort = args
+            .option::<Choice<PlayedSort>>("sort")?
+            .map_or(PlayedSort::Time, |Choice(sort)| sort);
+        let limit = args.option::<usize>("limit")?;
+        let page = args.option::<usize>("page")?.unwrap_or(1);
+        let uid = args
+            .parse::<Option<UserId>>("user")?
+            .unwrap_or(msg.author.id);
 
-                let hours = (dur.num_seconds() / 60) / 60;
-                if hours > 0 {
-                    games_str.push_str(&format!("{}h", hours))
-                }
+        let entries = self.source.read(uid).await.context("read played entries")?;
+        let user = bot.cache.user(uid).context("unknown user")?;
 
-                let minutes = (dur.num_seconds() / 60) % 60;
-                if minutes > 0 {
-                    games_str.push_str(&format!("{}m", minutes))
-                }
+        let entries = match entries {
+            Some(entries) if !entries.games.is_empty() => entries,
+            _ => {
+                return Ok(Some(tr!(
+                    loc,
+                    "played-no-entries",
+                    user = format!("{}#{}", user.name, user.discriminator)
+                )))
+            }
+        };
 
-                let seconds = dur.num_seconds() % 60;
-                if seconds > 0 {
-                    games_str.push_str(&format!("{}s", seconds))
-                }
+        let mut games = entries.games;
+        let total = games.iter().map(|e| e.secs).sum::<i64>();
+        let count = games.len();
+        sort_games(&mut games, sort);
 
-                games_str.push_str("\n");
+        if let Some(limit) = limit {
+            if limit == 0 {
+                return Err(out_of_range("limit", 0, count).into());
             }
+            games.truncate(limit);
+        }
 
-            Ok(EmbedBuilder::new()
-                .title(&user.name)?
-                .description(format!(
-                    "*First seen {}, last updated {}*",
-                    time::format(res.first_seen),
-                    time::format(res.last_updated),
-                ))?
-                .thumbnail(ImageSource::url(fmt_user_avatar(user))?)
-                .timestamp(Utc::now().to_rfc3339())
-                .field(EmbedFieldBuilder::new("Games", games_str)?)
-                .color(0x79c879)?
-                .build()?)
+        let lines = games
+            .iter()
+            .map(|e| {
+                format!(
+                    "• **{}** {}\n",
+                    e.name,
+                    fmt_playtime(&loc, Duration::seconds(e.secs))
+                )
+            })
+            .collect::<Vec<_>>();
+        let pages = paginate(&lines);
+        if page == 0 || page > pages.len() {
+            return Err(out_of_range("page", page, pages.len()).into());
+        }
+
+        let mut embed = EmbedBuilder::new()
+            .title(&user.name)?
+            .description(tr!(
+                loc,
+                "played-seen",
+                first_seen = loc.relative(&entries.first_seen),
+                last_updated = loc.relative(&entries.last_updated),
+            ))?
+            .thumbnail(ImageSource::url(fmt_user_avatar(&user))?)
+            .timestamp(Utc::now().to_rfc3339())
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "played-games"),
+                pages[page - 1].clone(),
+            )?)
+            .field(EmbedFieldBuilder::new(
+                tr!(loc, "played-total"),
+                tr!(
+                    loc,
+                    "played-total-value",
+                    time = fmt_playtime(&loc, Duration::seconds(total)),
+                    games = count
+                ),
+            )?)
+            .color(0x79c879)?;
+        if pages.len() > 1 {
+            embed = embed.footer(EmbedFooterBuilder::new(tr!(
+                loc,
+                "played-page",
+                page = page,
+                pages = pages.len()
+            ))?);
         }
 
         bot.http
             .create_message(msg.channel_id)
-            .embed(embed(&user, entries)?)
+            .embed(embed.build()?)
             .context("set played embed")?
             .await
             .context("send played embed")?;
@@ -123,21 +187,523 @@ impl Command for Played {
 }
 
 impl Played {
-    pub async fn new() -> Played {
-        let fdb = foundationdb::Database::default().expect("open fdb");
+    /// Reads what members played from played-rs.
+    pub fn new() -> Played {
+        Played::with_source(PlayedRs::default())
+    }
+
+    pub fn with_source(source: impl PlayedSource + 'static) -> Played {
         Played {
-            c: Runner::new(fdb, ""),
+            source: Box::new(source),
+            top: Mutex::new(TimedCache::with_lifespan(TOP_CACHE_SECS)),
+            building: KeyedLocks::default(),
+        }
+    }
+
+    async fn receive_top(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        require_all(bot, msg, &[Requirement::Guild])?;
+
+        let gid = msg.guild_id.context("message didn't have guild id")?;
+        let board = self.leaderboard(bot, gid).await?;
+        if board.games.is_empty() {
+            return Ok(Some(tr!(loc, "played-top-empty")));
+        }
+
+        let description = if board.members < board.total_members {
+            tr!(
+                loc,
+                "played-top-sampled",
+                members = board.members,
+                total = board.total_members
+            )
+        } else {
+            tr!(loc, "played-top-description", members = board.members)
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title(tr!(loc, "played-top-title"))?
+            .description(description)?
+            .timestamp(board.built_at.to_rfc3339())
+            .color(0x79c879)?;
+        for (idx, game) in board.games.iter().enumerate() {
+            let players = game
+                .players
+                .iter()
+                .map(|(uid, secs)| {
+                    format!(
+                        "<@{}> {}",
+                        uid,
+                        fmt_playtime(&loc, Duration::seconds(*secs))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            embed = embed.field(EmbedFieldBuilder::new(
+                format!("{}. {}", idx + 1, game.name),
+                tr!(
+                    loc,
+                    "played-top-game",
+                    total = fmt_playtime(&loc, Duration::seconds(game.total)),
+                    players = players
+                ),
+            )?);
         }
+
+        bot.http
+            .create_message(msg.channel_id)
+            .allowed_mentions()
+            .build()
+            .embed(embed.build()?)
+            .context("set played top embed")?
+            .await
+            .context("send played top embed")?;
+
+        Ok(None)
+    }
+
+    /// Returns the leaderboard for `gid`, building it from cached members'
+    /// entries if there isn't a recent one.
+    async fn leaderboard(&self, bot: &Rikka, gid: GuildId) -> Result<Arc<Leaderboard>> {
+        if let Some(board) = self.cached(gid) {
+            return Ok(board);
+        }
+
+        let _guard = self.building.lock(gid).await;
+
+        // Someone else may have built it while we waited.
+        if let Some(board) = self.cached(gid) {
+            return Ok(board);
+        }
+
+        let board = Arc::new(self.build(bot, gid).await?);
+        self.top.lock().unwrap().cache_set(gid, board.clone());
+
+        Ok(board)
+    }
+
+    fn cached(&self, gid: GuildId) -> Option<Arc<Leaderboard>> {
+        self.top.lock().unwrap().cache_get(&gid).cloned()
+    }
+
+    /// Reads the entries of up to [`TOP_MAX_MEMBERS`] of `gid`'s members,
+    /// picked at random if there are more.
+    async fn build(&self, bot: &Rikka, gid: GuildId) -> Result<Leaderboard> {
+        let mut members = bot
+            .cache
+            .guild_members(gid)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|uid| bot.cache.member(gid, *uid).map_or(false, |m| !m.user.bot))
+            .collect::<Vec<_>>();
+        let total_members = members.len();
+        if total_members > TOP_MAX_MEMBERS {
+            members.shuffle(&mut rand::thread_rng());
+            members.truncate(TOP_MAX_MEMBERS);
+        }
+        let count = members.len();
+
+        let source = &*self.source;
+        let reads = stream::iter(members)
+            .map(|uid| async move { (uid, source.read(uid).await) })
+            .buffer_unordered(TOP_CONCURRENCY)
+            .filter_map(|(uid, res)| async move {
+                match res {
+                    Ok(entries) => entries.map(|entries| {
+                        let games = entries.games.into_iter().map(|e| (e.name, e.secs));
+                        (uid, games.collect())
+                    }),
+                    Err(err) => {
+                        println!("read played entries for {}: {}", uid, err);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(Leaderboard {
+            games: aggregate(reads),
+            members: count,
+            total_members,
+            built_at: Utc::now(),
+        })
     }
 }
 
-fn fmt_user_avatar(usr: &User) -> String {
-    let av = usr
-        .avatar
-        .as_ref()
-        .map(|av| format!("{}/{}", usr.id, av))
-        .unwrap_or((usr.discriminator.parse::<u16>().unwrap() % 5).to_string());
-    let ext = if av.starts_with("a_") { "gif" } else { "png" };
+/// Where [`Played`] reads what members have played from.
+#[async_trait]
+pub trait PlayedSource: Send + Sync {
+    /// Returns `uid`'s entries, or `None` if nothing was recorded for them.
+    async fn read(&self, uid: UserId) -> Result<Option<Entries>>;
+}
+
+/// What someone has played.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Entries {
+    pub first_seen: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+    pub games: Vec<GameEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameEntry {
+    pub name: String,
+    /// Seconds played in total.
+    pub secs: i64,
+    /// When it was last played, if that's known.
+    #[serde(default)]
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+/// Reads entries recorded by played-rs.
+#[derive(Default)]
+pub struct PlayedRs {
+    /// Opened the first time it's needed, so the command can be created
+    /// without a database, e.g. for the command reference.
+    c: Mutex<Option<&'static Runner>>,
+}
 
-    format!("https://cdn.discordapp.com/avatars/{}.{}", av, ext)
+impl PlayedRs {
+    /// Returns the played-rs runner, opening the database if it isn't yet.
+    fn runner(&self) -> Result<&'static Runner> {
+        let mut c = self.c.lock().unwrap();
+        if let Some(runner) = *c {
+            return Ok(runner);
+        }
+
+        let fdb = foundationdb::Database::default().context("open fdb")?;
+        let runner = Runner::new(fdb, "");
+        *c = Some(runner);
+
+        Ok(runner)
+    }
+}
+
+#[async_trait]
+impl PlayedSource for PlayedRs {
+    async fn read(&self, uid: UserId) -> Result<Option<Entries>> {
+        let res = self.runner()?.read(uid.0.to_string()).await?;
+        if res.games.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Entries {
+            first_seen: res.first_seen,
+            last_updated: res.last_updated,
+            games: res
+                .games
+                .into_iter()
+                // played-rs only records totals, so these sort last with
+                // `--sort recent`.
+                .map(|e| GameEntry {
+                    name: e.name,
+                    secs: e.dur as i64,
+                    last_played: None,
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// Keeps entries in [`Storage`], e.g. to run `played` without played-rs.
+pub struct StoredEntries {
+    storage: Arc<dyn Storage>,
+}
+
+const SUBSPACE_PREFIX: &[u8] = b"played";
+
+impl StoredEntries {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        StoredEntries { storage }
+    }
+
+    /// Replaces `uid`'s entries.
+    pub async fn write(&self, uid: UserId, entries: &Entries) -> Result<()> {
+        #[inline]
+        async fn exec(t: &dyn Transaction, kv: &(Vec<u8>, Vec<u8>)) -> Result<(), StorageError> {
+            t.set(&kv.0, &kv.1);
+            Ok(())
+        }
+
+        let kv = (fmt_entries_key(uid), serde_cbor::to_vec(entries)?);
+        transact(&*self.storage, kv, |tx, kv| exec(tx, kv).boxed()).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlayedSource for StoredEntries {
+    async fn read(&self, uid: UserId) -> Result<Option<Entries>> {
+        #[inline]
+        async fn exec(t: &dyn Transaction, key: &Vec<u8>) -> Result<Option<Vec<u8>>, StorageError> {
+            t.get(key).await
+        }
+
+        let key = fmt_entries_key(uid);
+        let raw = transact(&*self.storage, key, |tx, key| exec(tx, key).boxed()).await?;
+        match raw {
+            Some(raw) => Ok(Some(
+                serde_cbor::from_slice(&raw).context("decode entries")?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+fn fmt_entries_key(uid: UserId) -> Vec<u8> {
+    tuple::Subspace::all()
+        .subspace(&SUBSPACE_PREFIX)
+        .pack(&uid.0)
+}
+
+#[derive(EnumString, EnumVariantNames, Clone, Copy, Debug, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+enum PlayedSort {
+    Time,
+    Name,
+    Recent,
+}
+
+/// Sorts `games`, breaking ties by name. Games that were never seen being
+/// played sort last by [`PlayedSort::Recent`].
+fn sort_games(games: &mut [GameEntry], sort: PlayedSort) {
+    let by_name = |a: &GameEntry, b: &GameEntry| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+    match sort {
+        PlayedSort::Time => games.sort_by(|a, b| b.secs.cmp(&a.secs).then_with(|| by_name(a, b))),
+        PlayedSort::Name => games.sort_by(by_name),
+        PlayedSort::Recent => games.sort_by(|a, b| {
+            b.last_played
+                .cmp(&a.last_played)
+                .then_with(|| by_name(a, b))
+        }),
+    }
+}
+
+/// Formats a playtime as e.g. `12h3m`, leaving out parts that are zero.
+fn fmt_playtime(loc: &Locale, dur: Duration) -> String {
+    let mut out = String::new();
+
+    let hours = (dur.num_seconds() / 60) / 60;
+    if hours > 0 {
+        out.push_str(&format!("{}h", loc.number(hours)))
+    }
+
+    let minutes = (dur.num_seconds() / 60) % 60;
+    if minutes > 0 {
+        out.push_str(&format!("{}m", minutes))
+    }
+
+    let seconds = dur.num_seconds() % 60;
+    if seconds > 0 {
+        out.push_str(&format!("{}s", seconds))
+    }
+
+    out
+}
+
+/// Splits `lines` into pages of at most [`PAGE_SIZE`] lines that each fit in
+/// an embed field.
+fn paginate(lines: &[String]) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut page = String::new();
+    let mut len = 0;
+
+    for line in lines {
+        let full = len == PAGE_SIZE || page.chars().count() + line.chars().count() > FIELD_LIMIT;
+        if full && !page.is_empty() {
+            pages.push(std::mem::take(&mut page));
+            len = 0;
+        }
+        page.push_str(line);
+        len += 1;
+    }
+    pages.push(page);
+
+    pages
+}
+
+/// Sums each member's `(game, seconds)` entries into the most played games,
+/// each with the members who played it most.
+fn aggregate(reads: Vec<(UserId, Vec<(String, i64)>)>) -> Vec<GameTotal> {
+    let mut games: HashMap<String, (i64, Vec<(UserId, i64)>)> = HashMap::new();
+    for (uid, entries) in reads {
+        for (name, secs) in entries.into_iter().filter(|(_, secs)| *secs > 0) {
+            let game = games.entry(name).or_default();
+            game.0 += secs;
+            game.1.push((uid, secs));
+        }
+    }
+
+    let mut games = games
+        .into_iter()
+        .map(|(name, (total, mut players))| {
+            players.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            players.truncate(TOP_PLAYERS);
+            GameTotal {
+                name,
+                total,
+                players,
+            }
+        })
+        .collect::<Vec<_>>();
+    games.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+    games.truncate(TOP_GAMES);
+
+    games
+}
+
+fn out_of_range(name: &str, value: usize, max: usize) -> ArgError {
+    ArgError::Invalid {
+        usage: option_usage::<usize>(name),
+        expected: Expected::Range("1".into(), max.to_string()),
+        value: value.to_string(),
+    }
+}
+
+/// A guild's most played games.
+struct Leaderboard {
+    games: Vec<GameTotal>,
+    /// How many members' entries were read.
+    members: usize,
+    /// How many members the guild had, which is more than `members` if they
+    /// were sampled.
+    total_members: usize,
+    built_at: DateTime<Utc>,
+}
+
+#[derive(Debug, PartialEq)]
+struct GameTotal {
+    name: String,
+    /// Seconds played by every member together.
+    total: i64,
+    /// The members who played it most, with their seconds played.
+    players: Vec<(UserId, i64)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: u64, games: &[(&str, i64)]) -> (UserId, Vec<(String, i64)>) {
+        (
+            UserId(id),
+            games.iter().map(|(n, s)| (n.to_string(), *s)).collect(),
+        )
+    }
+
+    /// Games with playtimes and how many hours ago they were last played.
+    fn games(games: &[(&str, i64, Option<i64>)]) -> Vec<GameEntry> {
+        let now = Utc::now();
+        games
+            .iter()
+            .map(|(name, secs, ago)| GameEntry {
+                name: name.to_string(),
+                secs: *secs,
+                last_played: ago.map(|h| now - Duration::hours(h)),
+            })
+            .collect()
+    }
+
+    fn names(games: &[GameEntry]) -> Vec<&str> {
+        games.iter().map(|g| g.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_games_by_time_and_name() {
+        let mut list = games(&[("b", 10, None), ("C", 30, None), ("a", 10, None)]);
+        sort_games(&mut list, PlayedSort::Time);
+        assert_eq!(names(&list), ["C", "a", "b"]);
+
+        sort_games(&mut list, PlayedSort::Name);
+        assert_eq!(names(&list), ["a", "b", "C"]);
+    }
+
+    #[test]
+    fn sort_games_by_recent() {
+        let mut list = games(&[
+            ("old", 50, Some(48)),
+            ("unknown", 90, None),
+            ("new", 10, Some(1)),
+            ("also unknown", 5, None),
+        ]);
+        sort_games(&mut list, PlayedSort::Recent);
+        assert_eq!(names(&list), ["new", "old", "also unknown", "unknown"]);
+    }
+
+    #[test]
+    fn paginate_splits_by_count() {
+        let lines = (0..PAGE_SIZE * 2 + 1)
+            .map(|i| format!("{}\n", i))
+            .collect::<Vec<_>>();
+        let pages = paginate(&lines);
+
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0], lines[..PAGE_SIZE].concat());
+        assert_eq!(pages[1], lines[PAGE_SIZE..PAGE_SIZE * 2].concat());
+        assert_eq!(pages[2], lines[PAGE_SIZE * 2]);
+    }
+
+    #[test]
+    fn paginate_splits_by_length() {
+        let line = format!("{}\n", "x".repeat(299));
+        let pages = paginate(&vec![line.clone(); 5]);
+
+        assert_eq!(pages, vec![line.repeat(3), line.repeat(2)]);
+        assert!(pages.iter().all(|p| p.chars().count() <= FIELD_LIMIT));
+    }
+
+    #[test]
+    fn paginate_keeps_overlong_lines() {
+        let long = "x".repeat(FIELD_LIMIT + 1);
+        let pages = paginate(&["a\n".to_string(), long.clone()]);
+        assert_eq!(pages, vec!["a\n".to_string(), long]);
+
+        assert_eq!(paginate(&[]), vec![String::new()]);
+    }
+
+    #[test]
+    fn aggregate_sums_and_ranks() {
+        let games = aggregate(vec![
+            user(1, &[("Celeste", 100), ("Hades", 50)]),
+            user(2, &[("Hades", 300), ("Celeste", 0)]),
+            user(3, &[("Hades", 10)]),
+        ]);
+
+        assert_eq!(
+            games,
+            vec![
+                GameTotal {
+                    name: "Hades".into(),
+                    total: 360,
+                    players: vec![(UserId(2), 300), (UserId(1), 50), (UserId(3), 10)],
+                },
+                GameTotal {
+                    name: "Celeste".into(),
+                    total: 100,
+                    players: vec![(UserId(1), 100)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_truncates() {
+        let reads = (0..TOP_GAMES as u64 + 2)
+            .map(|id| (UserId(id), vec![(format!("game {}", id), 10)]))
+            .chain((10..15).map(|id| user(id, &[("shared", id as i64)])))
+            .collect();
+        let games = aggregate(reads);
+
+        assert_eq!(games.len(), TOP_GAMES);
+        assert_eq!(games[0].name, "shared");
+        assert_eq!(
+            games[0].players,
+            vec![(UserId(14), 14), (UserId(13), 13), (UserId(12), 12)]
+        );
+        // Ties are broken by name.
+        assert_eq!(games[1].name, "game 0");
+    }
 }
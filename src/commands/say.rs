@@ -0,0 +1,219 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+use twilight_embed_builder::{
+    image_source::ImageSource, EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder,
+};
+use twilight_model::channel::{embed::Embed, GuildChannel, Message};
+use twilight_model::guild::Permissions;
+
+use crate::error::{ArgError, CommandError, CommandResult, Expected};
+use crate::extract::{flag_usage, usage, usage_of, Rest};
+use crate::help::{CommandHelp, HelpSection};
+use crate::parse::matches_command;
+use crate::permissions::{channel_permissions, fmt_permissions, require_all, Requirement};
+use crate::rikka::{Command, Rikka};
+
+/// Posts text, or an embed, as the bot without pinging anyone.
+pub struct Say;
+
+const SAY_ALIAS: &[&'static str] = &["say"];
+
+#[async_trait]
+impl Command for Say {
+    fn name(&self) -> &'static str {
+        "say"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "say",
+            section: HelpSection::Moderation,
+            usage: usage_of(&[
+                usage::<Option<Arc<GuildChannel>>>("channel"),
+                usage::<Rest>("text"),
+                flag_usage("embed"),
+                flag_usage("delete"),
+            ]),
+            examples: &[
+                "say Hello!",
+                "say #announcements Maintenance tonight --delete",
+                "say --embed title: Rules\ndescription: Be nice\ncolor: #e91e63",
+                "say --embed {\"title\": \"Rules\", \"description\": \"Be nice\"}",
+            ],
+            requires: requires(),
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let mut args = matches_command(bot, msg, SAY_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+        require_all(bot, msg, &requires())?;
+
+        let as_embed = args.flag("embed");
+        let delete = args.flag("delete");
+        // Only treat the first word as a channel if it's a mention, so text
+        // that happens to start with a number is still said.
+        let target = match args.clone().next() {
+            Some(arg) if arg.starts_with("<#") => args.parse::<Arc<GuildChannel>>("channel")?.id(),
+            _ => msg.channel_id,
+        };
+        let text = args.parse::<Rest>("text")?.0;
+
+        let gid = msg.guild_id.context("message didn't have guild id")?;
+        let missing = (Permissions::SEND_MESSAGES | Permissions::MANAGE_MESSAGES)
+            - channel_permissions(bot, gid, target, msg.author.id);
+        if !missing.is_empty() && !bot.is_owner(msg.author.id) {
+            return Err(CommandError::Denied(tr!(
+                loc,
+                "requires-permissions",
+                permissions = fmt_permissions(missing)
+            )));
+        }
+
+        let create = bot.http.create_message(target).allowed_mentions().build();
+        if as_embed {
+            create
+                .embed(parse_embed(&text)?)
+                .context("build embed")?
+                .await
+                .context("send message")?;
+        } else {
+            create
+                .content(text.into_owned())
+                .context("add content")?
+                .await
+                .context("send message")?;
+        }
+
+        if delete {
+            bot.http
+                .delete_message(msg.channel_id, msg.id)
+                .await
+                .context("delete invoking message")?;
+        }
+
+        Ok(None)
+    }
+}
+
+fn requires() -> Vec<Requirement> {
+    vec![
+        Requirement::Guild,
+        Requirement::Permissions(Permissions::MANAGE_MESSAGES),
+    ]
+}
+
+fn parse_embed(text: &str) -> Result<Embed, ArgError> {
+    let invalid = || ArgError::Malformed {
+        usage: usage::<Rest>("text"),
+        expected: Expected::Embed,
+    };
+
+    let spec = EmbedSpec::parse(text).ok_or_else(invalid)?;
+    spec.build().map_err(|_| invalid())
+}
+
+/// A small embed, written as JSON or as `key: value` lines.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct EmbedSpec {
+    title: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    /// A hex color like `#e91e63`.
+    color: Option<String>,
+    footer: Option<String>,
+    image: Option<String>,
+    thumbnail: Option<String>,
+    fields: Vec<FieldSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldSpec {
+    name: String,
+    value: String,
+    #[serde(default)]
+    inline: bool,
+}
+
+impl EmbedSpec {
+    fn parse(text: &str) -> Option<EmbedSpec> {
+        let text = text.trim();
+        if text.starts_with('{') {
+            return serde_json::from_str(text).ok();
+        }
+
+        let mut spec = EmbedSpec::default();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (key, rest) = split_pair(line)?;
+            let value = Some(rest.to_owned());
+
+            match key.to_lowercase().as_str() {
+                "title" => spec.title = value,
+                "description" => spec.description = value,
+                "url" => spec.url = value,
+                "color" | "colour" => spec.color = value,
+                "footer" => spec.footer = value,
+                "image" => spec.image = value,
+                "thumbnail" => spec.thumbnail = value,
+                "field" => {
+                    let (name, value) = split_pair(rest)?;
+                    spec.fields.push(FieldSpec {
+                        name: name.to_owned(),
+                        value: value.to_owned(),
+                        inline: false,
+                    });
+                }
+                _ => return None,
+            }
+        }
+
+        Some(spec)
+    }
+
+    fn build(self) -> Result<Embed> {
+        let mut embed = EmbedBuilder::new();
+        if let Some(title) = self.title {
+            embed = embed.title(title)?;
+        }
+        if let Some(description) = self.description {
+            embed = embed.description(description)?;
+        }
+        if let Some(url) = self.url {
+            embed = embed.url(url);
+        }
+        if let Some(color) = self.color {
+            let color = u32::from_str_radix(color.trim_start_matches('#'), 16)
+                .map_err(|_| anyhow!("invalid color {}", color))?;
+            embed = embed.color(color)?;
+        }
+        if let Some(footer) = self.footer {
+            embed = embed.footer(EmbedFooterBuilder::new(footer)?);
+        }
+        if let Some(image) = self.image {
+            embed = embed.image(ImageSource::url(image)?);
+        }
+        if let Some(thumbnail) = self.thumbnail {
+            embed = embed.thumbnail(ImageSource::url(thumbnail)?);
+        }
+        for field in self.fields {
+            let mut builder = EmbedFieldBuilder::new(field.name, field.value)?;
+            if field.inline {
+                builder = builder.inline();
+            }
+            embed = embed.field(builder);
+        }
+
+        Ok(embed.build()?)
+    }
+}
+
+/// Splits `key: value` at the first colon.
+fn split_pair(line: &str) -> Option<(&str, &str)> {
+    let idx = line.find(':')?;
+    Some((line[..idx].trim(), line[idx + 1..].trim()))
+}
@@ -0,0 +1,150 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::FutureExt;
+use twilight_model::channel::Message;
+use twilight_model::guild::Permissions;
+
+use crate::bus::{BotEvent, SettingChanged};
+use crate::error::CommandResult;
+use crate::extract::{usage, Choice};
+use crate::help::{CommandHelp, HelpSection};
+use crate::parse::{matches_command, Args};
+use crate::permissions::Requirement;
+use crate::rikka::{Command, Rikka};
+use crate::settings::{self, Toggle};
+use crate::subcommand::{Subcommand, SubcommandInfo};
+
+pub struct Settings {
+    tree: Subcommand<Settings>,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            tree: Settings::tree(),
+        }
+    }
+
+    fn tree() -> Subcommand<Settings> {
+        Subcommand::new("settings")
+            .aliases(&["config"])
+            .requires(Requirement::Guild)
+            .requires(Requirement::Permissions(Permissions::MANAGE_GUILD))
+            .child(
+                Subcommand::new("suggestions")
+                    .usage(usage::<Option<Choice<Toggle>>>("state"))
+                    .examples(&["settings suggestions", "settings suggestions off"])
+                    .handler(|_, bot, msg, args| {
+                        Settings::handle_suggestions(bot, msg, args).boxed()
+                    }),
+            )
+            .child(
+                Subcommand::new("language")
+                    .aliases(&["lang"])
+                    .usage(usage::<Option<String>>("language"))
+                    .examples(&["settings language", "settings language de"])
+                    .handler(|_, bot, msg, args| Settings::handle_language(bot, msg, args).boxed()),
+            )
+    }
+
+    async fn handle_suggestions<'a>(
+        bot: &Rikka,
+        msg: &Message,
+        mut args: Args<'a>,
+    ) -> CommandResult {
+        let gid = msg
+            .guild_id
+            .ok_or(Error::msg("message didn't have guild id"))?;
+
+        let enabled = match args.parse::<Option<Choice<Toggle>>>("state")? {
+            Some(Choice(state)) => {
+                settings::set_bool(&*bot.storage, gid, settings::SUGGESTIONS, state.enabled())
+                    .await?;
+                bot.publish(BotEvent::SettingChanged(SettingChanged {
+                    guild_id: gid,
+                    user_id: msg.author.id,
+                    name: settings::SUGGESTIONS,
+                    value: if state.enabled() { "on" } else { "off" }.to_owned(),
+                }));
+                state.enabled()
+            }
+            None => settings::get_bool(&*bot.storage, gid, settings::SUGGESTIONS, true).await?,
+        };
+
+        Ok(Some(tr!(
+            bot.locale(msg.guild_id),
+            "settings-suggestions",
+            enabled = enabled.to_string()
+        )))
+    }
+
+    async fn handle_language<'a>(bot: &Rikka, msg: &Message, mut args: Args<'a>) -> CommandResult {
+        let gid = msg
+            .guild_id
+            .ok_or(Error::msg("message didn't have guild id"))?;
+
+        if let Some(tag) = args.parse::<Option<String>>("language")? {
+            let lang = match bot.i18n.find_language(&tag) {
+                Some(lang) => lang,
+                None => {
+                    return Ok(Some(tr!(
+                        bot.locale(msg.guild_id),
+                        "settings-language-unknown",
+                        language = tag,
+                        languages = bot
+                            .i18n
+                            .languages()
+                            .iter()
+                            .map(|l| format!("`{}`", l))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )))
+                }
+            };
+
+            settings::set(&*bot.storage, gid, settings::LANGUAGE, lang.as_bytes()).await?;
+            bot.i18n.set_guild_language(gid, lang);
+            bot.publish(BotEvent::SettingChanged(SettingChanged {
+                guild_id: gid,
+                user_id: msg.author.id,
+                name: settings::LANGUAGE,
+                value: lang.to_owned(),
+            }));
+        }
+
+        let loc = bot.locale(msg.guild_id);
+        Ok(Some(tr!(
+            loc,
+            "settings-language",
+            language = format!("{} (`{}`)", tr!(loc, "language-name"), loc.lang())
+        )))
+    }
+}
+
+const SETTINGS_ALIAS: &[&'static str] = &["settings", "config"];
+
+#[async_trait]
+impl Command for Settings {
+    fn name(&self) -> &'static str {
+        "settings"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "settings",
+            aliases: &["config"],
+            section: HelpSection::Moderation,
+            examples: &["settings suggestions off", "settings language de"],
+            ..Default::default()
+        }]
+    }
+
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        Some(&self.tree)
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        let args = matches_command(bot, msg, SETTINGS_ALIAS)?;
+        self.tree.dispatch(self, bot, msg, args).await
+    }
+}
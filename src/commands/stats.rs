@@ -0,0 +1,182 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use std::collections::BTreeMap;
+use twilight_embed_builder::{EmbedBuilder, EmbedFieldBuilder};
+use twilight_model::channel::Message;
+
+use crate::error::CommandResult;
+use crate::help::{CommandHelp, HelpSection};
+use crate::i18n::Locale;
+use crate::parse::matches_command;
+use crate::rikka::{Command, Rikka};
+
+/// Shows how the bot is doing.
+pub struct Stats;
+
+const STATS_ALIAS: &[&'static str] = &["stats", "botinfo"];
+
+#[async_trait]
+impl Command for Stats {
+    fn name(&self) -> &'static str {
+        "stats"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "stats",
+            aliases: &["botinfo"],
+            section: HelpSection::Info,
+            examples: &["stats"],
+            ..Default::default()
+        }]
+    }
+
+    async fn receive(&self, bot: &Rikka, msg: &Message) -> CommandResult {
+        matches_command(bot, msg, STATS_ALIAS)?;
+        let loc = bot.locale(msg.guild_id);
+
+        let guilds = bot.guild_ids();
+        let (channels, users) = bot.cached_counts();
+
+        let (_, tasks) = bot.concurrency_stats()[0];
+        let version = format!(
+            "{} ({})",
+            env!("CARGO_PKG_VERSION"),
+            option_env!("RIKKA_GIT_HASH").unwrap_or("unknown")
+        );
+
+        let embed = EmbedBuilder::new()
+            .title(tr!(loc, "stats-title"))?
+            .field(field(
+                tr!(loc, "stats-uptime"),
+                fmt_duration(Utc::now() - bot.started),
+            )?)
+            .field(field(tr!(loc, "stats-version"), version)?)
+            .field(field(
+                tr!(loc, "stats-guilds"),
+                loc.number(guilds.len() as i64),
+            )?)
+            .field(field(
+                tr!(loc, "stats-channels"),
+                loc.number(channels as i64),
+            )?)
+            .field(field(tr!(loc, "stats-users"), loc.number(users as i64))?)
+            .field(field(tr!(loc, "stats-shards"), fmt_shards(bot, &loc))?)
+            .field(field(tr!(loc, "stats-memory"), fmt_memory(&loc))?)
+            .field(field(
+                tr!(loc, "stats-tasks"),
+                tr!(
+                    loc,
+                    "stats-tasks-value",
+                    running = tasks.running,
+                    queued = tasks.queued
+                ),
+            )?)
+            .field(field(
+                tr!(loc, "stats-commands-run"),
+                loc.number(bot.commands_run() as i64),
+            )?)
+            .build()?;
+
+        bot.http
+            .create_message(msg.channel_id)
+            .embed(embed)
+            .context("build embed")?
+            .await
+            .context("send stats")?;
+        Ok(None)
+    }
+}
+
+fn field(name: String, value: String) -> anyhow::Result<EmbedFieldBuilder> {
+    Ok(EmbedFieldBuilder::new(name, value)?.inline())
+}
+
+/// Formats the number of shards and how many are in each stage, e.g.
+/// `2 (Connected: 2)`.
+fn fmt_shards(bot: &Rikka, loc: &Locale) -> String {
+    let info = bot.cluster.info();
+
+    let mut stages = BTreeMap::new();
+    for shard in info.values() {
+        *stages.entry(format!("{:?}", shard.stage())).or_insert(0) += 1;
+    }
+    let stages = stages
+        .iter()
+        .map(|(stage, count)| format!("{}: {}", stage, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if stages.is_empty() {
+        loc.number(info.len() as i64)
+    } else {
+        format!("{} ({})", loc.number(info.len() as i64), stages)
+    }
+}
+
+/// Formats how much memory jemalloc has handed out and how much it holds
+/// from the OS.
+fn fmt_memory(loc: &Locale) -> String {
+    // jemalloc caches its statistics until the epoch is advanced.
+    let stats = jemalloc_ctl::epoch::advance().and_then(|_| {
+        Ok((
+            jemalloc_ctl::stats::allocated::read()?,
+            jemalloc_ctl::stats::resident::read()?,
+        ))
+    });
+
+    match stats {
+        Ok((allocated, resident)) => tr!(
+            loc,
+            "stats-memory-value",
+            allocated = fmt_bytes(allocated),
+            resident = fmt_bytes(resident)
+        ),
+        Err(_) => tr!(loc, "stats-unknown"),
+    }
+}
+
+/// Formats a size in bytes with a binary unit, e.g. `12.3 MiB`.
+fn fmt_bytes(bytes: usize) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Formats a duration as e.g. `3d 4h 12m`, leaving out seconds once it's
+/// over an hour.
+fn fmt_duration(d: Duration) -> String {
+    let secs = d.num_seconds().max(0);
+    let (days, hours, mins, secs) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{}d", days));
+    }
+    if days > 0 || hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    parts.push(format!("{}m", mins));
+    if days == 0 && hours == 0 {
+        parts.push(format!("{}s", secs));
+    }
+
+    parts.join(" ")
+}
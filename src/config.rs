@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+/// The parts of the bot's configuration that can change while it runs,
+/// applied with [`crate::Rikka::apply_config`] and again on `bot reload`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// Presence templates to rotate through. See [`crate::Rikka::set_presence_templates`].
+    pub presences: Option<Vec<String>>,
+    /// How long each presence is shown.
+    pub presence_interval: Option<Duration>,
+}
+
+impl Config {
+    /// Reads the configuration from the environment. If `CONFIG_FILE` names
+    /// a file, its `KEY=value` lines override the environment, so it can be
+    /// edited without a restart.
+    pub fn load() -> Result<Config> {
+        let mut vars: HashMap<String, String> = env::vars().collect();
+        if let Some(path) = vars.get("CONFIG_FILE").cloned() {
+            let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path))?;
+            vars.extend(parse_file(&raw));
+        }
+
+        Ok(Config::from_vars(&vars))
+    }
+
+    /// Builds the configuration from variables named like the environment's:
+    ///
+    /// - `PRESENCES`: templates separated by semicolons, e.g.
+    ///   `r.help | {guilds} servers;r.help | {users} users`.
+    /// - `PRESENCE_INTERVAL_SECS`: seconds each presence is shown.
+    pub fn from_vars(vars: &HashMap<String, String>) -> Config {
+        Config {
+            presences: vars.get("PRESENCES").map(|presences| {
+                presences
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(String::from)
+                    .collect()
+            }),
+            presence_interval: vars
+                .get("PRESENCE_INTERVAL_SECS")
+                .and_then(|secs| secs.trim().parse().ok())
+                .map(Duration::from_secs),
+        }
+    }
+}
+
+/// Parses `KEY=value` lines, skipping blank ones and `#` comments.
+fn parse_file(raw: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next()?.trim();
+            let value = parts.next()?.trim();
+            Some((key.to_owned(), value.to_owned()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_lines_become_config() {
+        let raw = "# presences\nPRESENCES = a;  ; b \n\nPRESENCE_INTERVAL_SECS=60\nbroken\n";
+        let vars = parse_file(raw).collect();
+
+        assert_eq!(
+            Config::from_vars(&vars),
+            Config {
+                presences: Some(vec!["a".into(), "b".into()]),
+                presence_interval: Some(Duration::from_secs(60)),
+            }
+        );
+        assert_eq!(Config::from_vars(&HashMap::new()), Config::default());
+    }
+}
@@ -1,5 +1,11 @@
 use thiserror::Error as EnumError;
 
+use crate::format::truncate;
+use crate::i18n::Locale;
+
+/// How much of an invalid value is repeated back to the user.
+const VALUE_LIMIT: usize = 100;
+
 pub type CommandResult = Result<Option<String>, CommandError>;
 
 #[derive(EnumError, Debug)]
@@ -7,5 +13,149 @@ pub enum CommandError {
     #[error("command doesn't match")]
     NoMatch,
     #[error(transparent)]
+    BadArgument(#[from] ArgError),
+    /// The author isn't allowed to run the command, and why.
+    #[error("{0}")]
+    Denied(String),
+    /// The command was used wrong in a way that's already explained, e.g. an
+    /// unknown subcommand.
+    #[error("{0}")]
+    Rejected(String),
+    #[error(transparent)]
     Generic(#[from] anyhow::Error),
 }
+
+/// A user supplied argument that couldn't be used. These are shown to the
+/// user with [`ArgError::localize`], so they should read as complete
+/// sentences.
+#[derive(EnumError, Debug)]
+pub enum ArgError {
+    #[error("Missing argument `{0}`.")]
+    Missing(String),
+    #[error("Invalid argument `{usage}`: expected {expected}, got `{value}`.")]
+    Invalid {
+        usage: String,
+        expected: Expected,
+        value: String,
+    },
+    /// Like `Invalid`, for values too long or messy to repeat back.
+    #[error("Invalid argument `{usage}`: expected {expected}.")]
+    Malformed { usage: String, expected: Expected },
+}
+
+/// What kind of value an argument should have been.
+#[derive(Clone, Debug)]
+pub enum Expected {
+    Number,
+    UserId,
+    ChannelId,
+    RoleId,
+    User,
+    Member,
+    Channel,
+    Role,
+    Duration,
+    /// An embed written as JSON or `key: value` lines.
+    Embed,
+    Range(String, String),
+    OneOf(String),
+}
+
+/// The same wording as the default catalog, for logs.
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Expected::Number => f.write_str("a whole number"),
+            Expected::UserId => f.write_str("a user mention or id"),
+            Expected::ChannelId => f.write_str("a channel mention or id"),
+            Expected::RoleId => f.write_str("a role mention or id"),
+            Expected::User => f.write_str("a user I can see"),
+            Expected::Member => f.write_str("a member of this server"),
+            Expected::Channel => f.write_str("a channel in this server"),
+            Expected::Role => f.write_str("a role in this server"),
+            Expected::Duration => f.write_str("a duration like `1h30m`"),
+            Expected::Embed => f.write_str("an embed as JSON or `key: value` lines"),
+            Expected::Range(start, end) => write!(f, "a number from {} to {}", start, end),
+            Expected::OneOf(options) => write!(f, "one of {}", options),
+        }
+    }
+}
+
+impl ArgError {
+    pub fn localize(&self, loc: &Locale) -> String {
+        match self {
+            ArgError::Missing(usage) => tr!(loc, "arg-missing", usage = usage.as_str()),
+            ArgError::Invalid {
+                usage,
+                expected,
+                value,
+            } => tr!(
+                loc,
+                "arg-invalid",
+                usage = usage.as_str(),
+                expected = expected.localize(loc),
+                // Backticks would end the code span the value is shown in.
+                value = truncate(&value.replace('`', "'"), VALUE_LIMIT),
+            ),
+            ArgError::Malformed { usage, expected } => tr!(
+                loc,
+                "arg-malformed",
+                usage = usage.as_str(),
+                expected = expected.localize(loc),
+            ),
+        }
+    }
+}
+
+impl Expected {
+    pub fn localize(&self, loc: &Locale) -> String {
+        match self {
+            Expected::Number => tr!(loc, "expected-number"),
+            Expected::UserId => tr!(loc, "expected-user-id"),
+            Expected::ChannelId => tr!(loc, "expected-channel-id"),
+            Expected::RoleId => tr!(loc, "expected-role-id"),
+            Expected::User => tr!(loc, "expected-user"),
+            Expected::Member => tr!(loc, "expected-member"),
+            Expected::Channel => tr!(loc, "expected-channel"),
+            Expected::Role => tr!(loc, "expected-role"),
+            Expected::Duration => tr!(loc, "expected-duration"),
+            Expected::Embed => tr!(loc, "expected-embed"),
+            Expected::Range(start, end) => {
+                tr!(
+                    loc,
+                    "expected-range",
+                    start = start.as_str(),
+                    end = end.as_str()
+                )
+            }
+            Expected::OneOf(options) => tr!(loc, "expected-one-of", options = options.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_reads_as_english() {
+        let err = ArgError::Invalid {
+            usage: "--page <number>".into(),
+            expected: Expected::Range("1".into(), "3".into()),
+            value: "9".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument `--page <number>`: expected a number from 1 to 3, got `9`."
+        );
+
+        let err = ArgError::Malformed {
+            usage: "<embed>".into(),
+            expected: Expected::Embed,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument `<embed>`: expected an embed as JSON or `key: value` lines."
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::Duration;
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::Arc;
+use strum::VariantNames;
+use twilight_cache_inmemory::model::CachedMember;
+use twilight_mention::ParseMention;
+use twilight_model::channel::GuildChannel;
+use twilight_model::guild::Role;
+use twilight_model::id::{ChannelId, RoleId, UserId};
+use twilight_model::user::User;
+
+use crate::error::{ArgError, Expected};
+use crate::parse::{fmt_flag, Args};
+
+/// A value that can be pulled out of a command's arguments.
+pub trait Extract<'a>: Sized {
+    /// How an argument called `name` is shown in usage strings.
+    fn usage(name: &str) -> String {
+        format!("<{}>", name)
+    }
+
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError>;
+}
+
+/// Returns how an argument of type `T` called `name` is shown in usage
+/// strings, e.g. `[user]` for an `Option<UserId>`.
+pub fn usage<'a, T: Extract<'a>>(name: &str) -> String {
+    T::usage(name)
+}
+
+/// How a boolean flag called `name` is shown in usage strings.
+pub fn flag_usage(name: &str) -> String {
+    format!("[{}]", fmt_flag(name))
+}
+
+/// How an option called `name` taking a `T` is shown in usage strings, e.g.
+/// `[--limit <limit>]`.
+pub fn option_usage<'a, T: Extract<'a>>(name: &str) -> String {
+    format!("[{} {}]", fmt_flag(name), T::usage(name))
+}
+
+/// Joins the usage of several arguments, e.g.
+/// `usage_of(&[usage::<ChannelId>("channel"), usage::<Rest>("text")])`.
+pub fn usage_of(parts: &[String]) -> String {
+    parts.join(" ")
+}
+
+fn next<'a, T: Extract<'a>>(args: &mut Args<'a>, name: &str) -> Result<Cow<'a, str>, ArgError> {
+    args.next().ok_or_else(|| ArgError::Missing(T::usage(name)))
+}
+
+/// An [`ArgError::Invalid`] for an argument of type `T` called `name`.
+pub(crate) fn invalid<'a, T: Extract<'a>>(name: &str, expected: Expected, value: &str) -> ArgError {
+    ArgError::Invalid {
+        usage: T::usage(name),
+        expected,
+        value: value.to_owned(),
+    }
+}
+
+impl<'a> Extract<'a> for Cow<'a, str> {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        next::<Self>(args, name)
+    }
+}
+
+impl<'a> Extract<'a> for String {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        next::<Self>(args, name).map(Cow::into_owned)
+    }
+}
+
+macro_rules! extract_number {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> Extract<'a> for $ty {
+                fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+                    let raw = next::<Self>(args, name)?;
+                    raw.parse()
+                        .map_err(|_| invalid::<Self>(name, Expected::Number, &raw))
+                }
+            }
+        )*
+    };
+}
+
+extract_number!(u8, u16, u32, u64, usize, i32, i64);
+
+macro_rules! extract_id {
+    ($($ty:ident => $expected:expr),*) => {
+        $(
+            impl<'a> Extract<'a> for $ty {
+                fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+                    let raw = next::<Self>(args, name)?;
+                    $ty::parse(&raw)
+                        .ok()
+                        .or_else(|| raw.parse().ok().map($ty))
+                        .ok_or_else(|| invalid::<Self>(name, $expected, &raw))
+                }
+            }
+        )*
+    };
+}
+
+extract_id!(
+    UserId => Expected::UserId,
+    ChannelId => Expected::ChannelId,
+    RoleId => Expected::RoleId
+);
+
+impl<'a> Extract<'a> for Arc<User> {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = args.clone().next().unwrap_or_default();
+        let uid = args.parse::<UserId>(name)?;
+
+        args.bot
+            .cache
+            .user(uid)
+            .ok_or_else(|| invalid::<Self>(name, Expected::User, &raw))
+    }
+}
+
+impl<'a> Extract<'a> for Arc<CachedMember> {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = args.clone().next().unwrap_or_default();
+        let uid = args.parse::<UserId>(name)?;
+
+        args.msg
+            .guild_id
+            .and_then(|gid| args.bot.cache.member(gid, uid))
+            .ok_or_else(|| invalid::<Self>(name, Expected::Member, &raw))
+    }
+}
+
+impl<'a> Extract<'a> for Arc<GuildChannel> {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = args.clone().next().unwrap_or_default();
+        let cid = args.parse::<ChannelId>(name)?;
+
+        args.bot
+            .cache
+            .guild_channel(cid)
+            .filter(|ch| ch.guild_id().is_some() && ch.guild_id() == args.msg.guild_id)
+            .ok_or_else(|| invalid::<Self>(name, Expected::Channel, &raw))
+    }
+}
+
+impl<'a> Extract<'a> for Arc<Role> {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = args.clone().next().unwrap_or_default();
+        let rid = args.parse::<RoleId>(name)?;
+        let in_guild = args
+            .msg
+            .guild_id
+            .and_then(|gid| args.bot.cache.guild_roles(gid))
+            .map_or(false, |roles| roles.contains(&rid));
+
+        args.bot
+            .cache
+            .role(rid)
+            .filter(|_| in_guild)
+            .ok_or_else(|| invalid::<Self>(name, Expected::Role, &raw))
+    }
+}
+
+impl<'a> Extract<'a> for Duration {
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = next::<Self>(args, name)?;
+        parse_duration(&raw).ok_or_else(|| invalid::<Self>(name, Expected::Duration, &raw))
+    }
+}
+
+/// Parses durations like `1w2d`, `1h30m` or `90s`.
+pub fn parse_duration(raw: &str) -> Option<Duration> {
+    let mut total: i64 = 0;
+    let mut num = String::new();
+
+    for c in raw.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+
+        let unit = match c.to_ascii_lowercase() {
+            'w' => 7 * 24 * 60 * 60,
+            'd' => 24 * 60 * 60,
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let n: i64 = num.parse().ok()?;
+        num.clear();
+
+        total = total.checked_add(n.checked_mul(unit)?)?;
+    }
+
+    // chrono panics on durations it can't count in milliseconds.
+    if !num.is_empty() || raw.is_empty() || total > i64::MAX / 1000 {
+        return None;
+    }
+
+    Some(Duration::seconds(total))
+}
+
+/// One of a fixed set of options, parsed case insensitively. `T` should
+/// serialize its variants in lowercase, e.g. with
+/// `#[strum(serialize_all = "snake_case")]`.
+pub struct Choice<T>(pub T);
+
+impl<'a, T: FromStr + VariantNames> Extract<'a> for Choice<T> {
+    fn usage(_: &str) -> String {
+        format!("<{}>", T::VARIANTS.join("|"))
+    }
+
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let raw = next::<Self>(args, name)?;
+        let expected = Expected::OneOf(
+            T::VARIANTS
+                .iter()
+                .map(|v| format!("`{}`", v))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+
+        raw.to_lowercase()
+            .parse()
+            .map(Choice)
+            .map_err(|_| invalid::<Self>(name, expected, &raw))
+    }
+}
+
+/// Everything left in the arguments as it was typed, including quotes and
+/// flags that weren't extracted. Must not be empty.
+pub struct Rest<'a>(pub Cow<'a, str>);
+
+impl<'a> Extract<'a> for Rest<'a> {
+    fn usage(name: &str) -> String {
+        format!("<{}...>", name)
+    }
+
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let rest = args.rest();
+        if rest.trim().is_empty() {
+            return Err(ArgError::Missing(Self::usage(name)));
+        }
+
+        Ok(Rest(rest))
+    }
+}
+
+/// Optional arguments are `None` when there's nothing left to parse, but an
+/// argument that's present and invalid is still an error.
+impl<'a, T: Extract<'a>> Extract<'a> for Option<T> {
+    fn usage(name: &str) -> String {
+        format!("[{}]", strip_brackets(&T::usage(name)))
+    }
+
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        if args.is_empty() {
+            return Ok(None);
+        }
+
+        args.parse::<T>(name).map(Some)
+    }
+}
+
+/// Consumes every remaining argument as a `T`.
+impl<'a, T: Extract<'a>> Extract<'a> for Vec<T> {
+    fn usage(name: &str) -> String {
+        format!("[{}...]", strip_brackets(&T::usage(name)))
+    }
+
+    fn extract(args: &mut Args<'a>, name: &str) -> Result<Self, ArgError> {
+        let mut vals = Vec::new();
+        while !args.is_empty() {
+            vals.push(args.parse::<T>(name)?);
+        }
+
+        Ok(vals)
+    }
+}
+
+fn strip_brackets(usage: &str) -> &str {
+    usage.trim_start_matches('<').trim_end_matches('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90s"), Some(Duration::seconds(90)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("1W2d"), Some(Duration::days(9)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("1y"), None);
+        assert_eq!(parse_duration("m"), None);
+    }
+
+    #[test]
+    fn rejects_huge_durations() {
+        assert_eq!(parse_duration("9999999999999999s"), None);
+        assert_eq!(parse_duration("99999999999999999999w"), None);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, TimeZone, Utc};
+use twilight_model::id::GuildId;
+use twilight_model::user::User;
+
+use crate::i18n::Locale;
+
+/// How many characters a message's content may have.
+pub const MESSAGE_LIMIT: usize = 2000;
+
+const CDN: &str = "https://cdn.discordapp.com";
+/// The first millisecond of 2015, which Discord snowflakes count from.
+const DISCORD_EPOCH: i64 = 1_420_070_400_000;
+
+/// Links to a user's avatar, or the default avatar Discord shows for their
+/// discriminator if they haven't set one.
+pub fn fmt_user_avatar(usr: &User) -> String {
+    match usr.avatar.as_ref() {
+        Some(av) => format!("{}/avatars/{}/{}.{}", CDN, usr.id, av, image_ext(av)),
+        None => format!(
+            "{}/embed/avatars/{}.png",
+            CDN,
+            usr.discriminator.parse::<u16>().unwrap_or(0) % 5
+        ),
+    }
+}
+
+/// Links to a guild's icon from its hash.
+pub fn fmt_guild_icon(gid: GuildId, icon: &str) -> String {
+    format!("{}/icons/{}/{}.{}", CDN, gid, icon, image_ext(icon))
+}
+
+/// Animated images have hashes starting with `a_`.
+fn image_ext(hash: &str) -> &'static str {
+    if hash.starts_with("a_") {
+        "gif"
+    } else {
+        "png"
+    }
+}
+
+/// When the user, guild, channel or role with this id was created.
+pub fn snowflake_created(id: u64) -> DateTime<Utc> {
+    Utc.timestamp_millis((id >> 22) as i64 + DISCORD_EPOCH)
+}
+
+/// Cuts `s` down to at most `max` characters, ending it with `…` if anything
+/// was left out.
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_owned();
+    }
+
+    let mut out: String = s.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+/// Formats a date along with how long ago it was, e.g.
+/// `May 3, 2016 (4 years ago)`.
+pub fn fmt_date_relative(loc: &Locale, dt: &DateTime<Utc>) -> String {
+    tr!(
+        loc,
+        "fmt-date-relative",
+        date = loc.date(dt),
+        relative = loc.relative(dt)
+    )
+}
@@ -1,11 +1,17 @@
 use super::Rikka;
+use crate::i18n::Locale;
+use crate::permissions::{check_all, Requirement};
+use crate::rikka::Command;
+use crate::subcommand::SubcommandInfo;
 use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, EnumIter, IntoStaticStr};
 use twilight_embed_builder::{
     image_source::ImageSource, EmbedAuthorBuilder, EmbedBuilder, EmbedFieldBuilder,
 };
-use twilight_model::channel::embed::Embed;
+use twilight_model::channel::{embed::Embed, Message};
 
 #[derive(EnumIter, IntoStaticStr, AsRefStr, PartialEq, PartialOrd)]
 pub enum HelpSection {
@@ -16,47 +22,113 @@ pub enum HelpSection {
     Owner,
 }
 
+impl HelpSection {
+    /// The catalog key for the section's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            HelpSection::General => "section-general",
+            HelpSection::Fun => "section-fun",
+            HelpSection::Info => "section-info",
+            HelpSection::Moderation => "section-moderation",
+            HelpSection::Owner => "section-owner",
+        }
+    }
+}
+
 impl Default for HelpSection {
     fn default() -> Self {
         HelpSection::General
     }
 }
 
+/// A command's entry in the help. Its description and detailed help are in
+/// the catalogs, see [`help_description`].
 #[derive(Default)]
 pub struct CommandHelp {
     pub name: &'static str,
     pub aliases: &'static [&'static str],
     pub section: HelpSection,
-    pub description: &'static str,
-    pub usage: &'static str,
-    pub detailed: &'static str,
+    pub usage: String,
     pub examples: &'static [&'static str],
+    /// How long a user should wait between uses of the command, shown in
+    /// its detailed help.
+    pub cooldown: Option<Duration>,
+    /// What a user needs to run the command. Commands with a subcommand tree
+    /// put these on its root instead.
+    pub requires: Vec<Requirement>,
+}
+
+/// The one line description of the command at `path`, e.g. `log message`,
+/// from the catalog key `help-log-message-description`. Commands missing
+/// from the catalogs get an empty string.
+pub fn help_description(loc: &Locale, path: &str) -> String {
+    help_text(loc, path, "description")
+}
+
+/// Explains more about the command at `path` on its help page, from the
+/// catalog key `help-<path>-detailed`.
+pub fn help_detailed(loc: &Locale, path: &str) -> String {
+    help_text(loc, path, "detailed")
+}
+
+fn help_text(loc: &Locale, path: &str, part: &str) -> String {
+    let key = format!("help-{}-{}", path.replace(' ', "-"), part);
+    loc.get(&key, None).unwrap_or_default()
+}
+
+/// Which commands a help listing includes.
+#[derive(Clone, Copy)]
+pub enum Listing<'a> {
+    /// Commands the message's author can run where it was sent.
+    Here(&'a Message),
+    /// Commands the message's author can run anywhere, for help sent to
+    /// their DMs.
+    Anywhere(&'a Message),
 }
 
-pub fn generate_help<'a>(bot: &Rikka) -> Result<Embed> {
+impl<'a> Listing<'a> {
+    pub fn message(self) -> &'a Message {
+        match self {
+            Listing::Here(msg) | Listing::Anywhere(msg) => msg,
+        }
+    }
+
+    /// Whether a command needing `reqs` is listed.
+    pub fn allows(self, bot: &Rikka, reqs: &[Requirement]) -> bool {
+        match self {
+            Listing::Here(msg) => check_all(bot, msg, reqs).is_none(),
+            Listing::Anywhere(msg) => reqs.iter().all(|req| req.attainable(bot, msg)),
+        }
+    }
+}
+
+/// Renders the command list. With `listing`, only the commands it allows are
+/// listed.
+pub fn generate_help<'a>(bot: &Rikka, loc: &Locale, listing: Option<Listing>) -> Result<Embed> {
     let av_url = String::from("https://cdn.discordapp.com/avatars/319571495666057227/b14a77bf6f87d2ccc4a9c2d4e52cfe4b.webp?size=1024");
     let mut embed = EmbedBuilder::new()
         .author(
             EmbedAuthorBuilder::new()
-                .name("Rikka v3 Command Help")?
+                .name(tr!(loc, "help-author"))?
                 .url("https://github.com/coadler/rikka.rs")
                 .icon_url(ImageSource::url(&av_url)?)
                 .build(),
         )
         .thumbnail(ImageSource::url(av_url)?)
-        .title("Join our server for more information")?
+        .title(tr!(loc, "help-title"))?
         .url("https://discord.gg/Na6knqq")
-        .description(&format!(
-            "Type `{}help [command]` for detailed usage information",
-            &bot.prefix
-        ))?;
+        .description(tr!(loc, "help-description", prefix = bot.prefix.as_str()))?;
 
     let mut buf = String::new();
 
     for sect in HelpSection::iter() {
-        for cmd in &bot.cmds {
-            for help in cmd.help(None) {
-                if help.section == sect {
+        for cmd in bot.commands() {
+            for help in cmd.help(listing.map(Listing::message)) {
+                let allowed = listing.map_or(true, |listing| {
+                    listing.allows(bot, &requirements(&*cmd, &help))
+                });
+
+                if help.section == sect && allowed {
                     if buf.len() > 0 {
                         buf.push_str(", ");
                     }
@@ -67,10 +139,313 @@ pub fn generate_help<'a>(bot: &Rikka) -> Result<Embed> {
         }
 
         if buf.len() > 0 {
-            embed = embed.field(EmbedFieldBuilder::new(sect.as_ref(), &buf)?.inline());
+            embed = embed.field(EmbedFieldBuilder::new(tr!(loc, sect.key()), &buf)?.inline());
             buf.clear();
         }
     }
 
     Ok(embed.build()?)
 }
+
+/// Renders the detailed help page for `path`, e.g. `["log", "message"]`.
+/// `node` is the subcommand the path leads to for commands with a tree, and
+/// `requires` collects the requirements of every node along the way.
+pub fn generate_command_help(
+    bot: &Rikka,
+    loc: &Locale,
+    help: &CommandHelp,
+    path: &[&str],
+    node: Option<&dyn SubcommandInfo>,
+    requires: &[Requirement],
+) -> Result<Embed> {
+    let root = path.len() == 1;
+    let path = path.join(" ");
+    let invocation = format!("{}{}", bot.prefix, path);
+
+    let (usage, aliases, examples) = match node {
+        Some(node) if !root => (node.usage(), node.aliases(), node.examples()),
+        _ => (help.usage.as_str(), help.aliases, help.examples),
+    };
+
+    let mut description = help_description(loc, &path);
+    let detailed = help_detailed(loc, &path);
+    if !detailed.is_empty() {
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&detailed);
+    }
+
+    let mut embed = EmbedBuilder::new().title(&invocation)?;
+    if !description.is_empty() {
+        embed = embed.description(description)?;
+    }
+
+    let usage = format!("{} {}", invocation, usage);
+    embed = embed.field(EmbedFieldBuilder::new(
+        tr!(loc, "help-usage"),
+        format!("`{}`", usage.trim_end()),
+    )?);
+
+    if !aliases.is_empty() {
+        embed = embed.field(EmbedFieldBuilder::new(
+            tr!(loc, "help-aliases"),
+            fmt_code_list(aliases),
+        )?);
+    }
+
+    let children = node.map(|n| n.children()).unwrap_or_default();
+    if !children.is_empty() {
+        let mut buf = String::new();
+        for child in children {
+            buf.push_str(&format!("\n`{}`", child.name()));
+            if !child.usage().is_empty() {
+                buf.push_str(&format!(" `{}`", child.usage()));
+            }
+            let description = help_description(loc, &format!("{} {}", path, child.name()));
+            if !description.is_empty() {
+                buf.push_str(&format!(" - {}", description));
+            }
+        }
+        embed = embed.field(EmbedFieldBuilder::new(
+            tr!(loc, "help-subcommands"),
+            buf.trim_start(),
+        )?);
+    }
+
+    if !examples.is_empty() {
+        let examples = examples
+            .iter()
+            .map(|ex| format!("`{}{}`", bot.prefix, ex))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field(EmbedFieldBuilder::new(tr!(loc, "help-examples"), examples)?);
+    }
+
+    if !requires.is_empty() {
+        let requires = requires
+            .iter()
+            .map(|req| req.describe(loc))
+            .collect::<Vec<_>>()
+            .join(", ");
+        embed = embed.field(EmbedFieldBuilder::new(tr!(loc, "help-requires"), requires)?.inline());
+    }
+
+    if let Some(cooldown) = help.cooldown {
+        embed = embed.field(
+            EmbedFieldBuilder::new(
+                tr!(loc, "help-cooldown"),
+                tr!(loc, "help-cooldown-value", seconds = cooldown.as_secs()),
+            )?
+            .inline(),
+        );
+    }
+
+    Ok(embed.build()?)
+}
+
+/// Formats names as e.g. `` `logs`, `log` ``.
+fn fmt_code_list(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Everything needed to run `cmd`, from its subcommand tree's root and its
+/// help entry.
+pub fn requirements(cmd: &dyn Command, help: &CommandHelp) -> Vec<Requirement> {
+    let mut reqs = cmd
+        .subcommands()
+        .map(|root| root.requires().to_vec())
+        .unwrap_or_default();
+    reqs.extend_from_slice(&help.requires);
+    reqs
+}
+
+/// How many results `help search` shows.
+const SEARCH_RESULTS: usize = 5;
+
+/// Renders the best matches for `query`, or `None` if nothing matched.
+pub fn generate_search(
+    bot: &Rikka,
+    loc: &Locale,
+    listing: Listing,
+    query: &str,
+) -> Result<Option<Embed>> {
+    let results = search(bot, loc, listing, query, SEARCH_RESULTS);
+    if results.is_empty() {
+        return Ok(None);
+    }
+
+    let mut buf = String::new();
+    for res in results {
+        let usage = format!("{}{} {}", bot.prefix, res.path, res.usage);
+        buf.push_str(&format!("\n`{}`", usage.trim_end()));
+        if !res.description.is_empty() {
+            buf.push_str(&format!(" - {}", res.description));
+        }
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(tr!(loc, "help-search-title", query = query))?
+        .description(buf.trim_start())?
+        .build()?;
+    Ok(Some(embed))
+}
+
+/// How much a match in each part of a command's help counts for in
+/// [`search`]. Whole word matches count double.
+const NAME_WEIGHT: usize = 4;
+const ALIAS_WEIGHT: usize = 3;
+const DESCRIPTION_WEIGHT: usize = 2;
+const DETAILED_WEIGHT: usize = 1;
+
+/// Words too common to say anything about what a command does.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "command", "for", "how", "i", "in", "is", "it", "of", "that", "the",
+    "thing", "this", "to", "what", "which", "with",
+];
+
+/// A command or subcommand found by [`search`].
+pub struct SearchResult {
+    /// The full path to the command, e.g. `log message`.
+    pub path: String,
+    pub usage: String,
+    pub description: String,
+    score: usize,
+}
+
+/// Ranks the commands and subcommands `listing` allows by how well their
+/// names, aliases, descriptions and detailed help match the words in `query`,
+/// returning at most `limit` of them. Descriptions are searched in `loc`'s
+/// language.
+pub fn search(
+    bot: &Rikka,
+    loc: &Locale,
+    listing: Listing,
+    query: &str,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let words: Vec<String> = tokenize(query)
+        .into_iter()
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect();
+    let mut results = Vec::new();
+
+    for cmd in bot.commands() {
+        for help in cmd.help(Some(listing.message())) {
+            if !listing.allows(bot, &requirements(&*cmd, &help)) {
+                continue;
+            }
+
+            let description = help_description(loc, help.name);
+            let score = score(&words, &[help.name], NAME_WEIGHT)
+                + score(&words, help.aliases, ALIAS_WEIGHT)
+                + score(&words, &[description.as_str()], DESCRIPTION_WEIGHT)
+                + score(
+                    &words,
+                    &[help_detailed(loc, help.name).as_str()],
+                    DETAILED_WEIGHT,
+                );
+            results.push(SearchResult {
+                path: help.name.to_owned(),
+                usage: help.usage.clone(),
+                description,
+                score,
+            });
+
+            if let Some(root) = cmd.subcommands() {
+                for child in root.children() {
+                    search_node(bot, loc, listing, &words, help.name, child, &mut results);
+                }
+            }
+        }
+    }
+
+    results.retain(|r| r.score > 0);
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    results.truncate(limit);
+    results
+}
+
+fn search_node(
+    bot: &Rikka,
+    loc: &Locale,
+    listing: Listing,
+    words: &[String],
+    parent: &str,
+    node: &dyn SubcommandInfo,
+    results: &mut Vec<SearchResult>,
+) {
+    if !listing.allows(bot, node.requires()) {
+        return;
+    }
+
+    let path = format!("{} {}", parent, node.name());
+    for child in node.children() {
+        search_node(bot, loc, listing, words, &path, child, results);
+    }
+
+    let description = help_description(loc, &path);
+    results.push(SearchResult {
+        score: score(words, &[node.name()], NAME_WEIGHT)
+            + score(words, node.aliases(), ALIAS_WEIGHT)
+            + score(words, &[description.as_str()], DESCRIPTION_WEIGHT),
+        path,
+        usage: node.usage().to_owned(),
+        description,
+    });
+}
+
+/// Scores how well `words` match `texts`. Each word counts once, for its
+/// best match: `weight` if it's a prefix of a word in the text, or the
+/// other way around, and twice that if it's the whole word.
+fn score(words: &[String], texts: &[&str], weight: usize) -> usize {
+    let tokens: Vec<String> = texts.iter().flat_map(|text| tokenize(text)).collect();
+
+    words
+        .iter()
+        .map(|word| {
+            tokens
+                .iter()
+                .map(|tok| {
+                    if tok == word {
+                        weight * 2
+                    } else if word.len() >= 3
+                        && tok.len() >= 3
+                        && (tok.starts_with(word) || word.starts_with(tok.as_str()))
+                    {
+                        weight
+                    } else {
+                        0
+                    }
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+/// Splits `text` into lowercase words, ignoring punctuation.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Finds the registered command with a help entry named `name`, matching
+/// aliases too.
+pub fn find_command(bot: &Rikka, name: &str) -> Option<(Arc<dyn Command>, CommandHelp)> {
+    for cmd in bot.commands() {
+        for help in cmd.help(None) {
+            if help.name == name || help.aliases.contains(&name) {
+                return Some((cmd, help));
+            }
+        }
+    }
+
+    None
+}
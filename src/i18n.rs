@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use twilight_model::id::GuildId;
+use unic_langid::LanguageIdentifier;
+
+/// Used when a guild hasn't picked a language, and for messages missing from
+/// a guild's language.
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US/main.ftl")),
+    ("de", include_str!("../locales/de/main.ftl")),
+];
+
+/// Translates a message from the catalog, e.g.
+/// `tr!(loc, "logs-enabled", channel = ch.name())`.
+macro_rules! tr {
+    ($loc:expr, $key:expr) => {
+        $loc.tr($key, None)
+    };
+    ($loc:expr, $key:expr, $($name:ident = $val:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.insert(stringify!($name), fluent_bundle::FluentValue::from($val));)+
+        $loc.tr($key, Some(&args))
+    }};
+}
+
+/// The message catalogs for every supported language, and which language
+/// each guild uses.
+pub struct I18n {
+    bundles: HashMap<&'static str, FluentBundle<FluentResource>>,
+    guilds: RwLock<HashMap<GuildId, &'static str>>,
+}
+
+impl I18n {
+    pub fn new() -> Self {
+        let bundles = CATALOGS
+            .iter()
+            .map(|(tag, src)| {
+                let lang: LanguageIdentifier = tag.parse().expect("valid language tag");
+                let res = FluentResource::try_new(src.to_string())
+                    .unwrap_or_else(|(_, errs)| panic!("invalid {} catalog: {:?}", tag, errs));
+
+                let mut bundle = FluentBundle::new(&[lang]);
+                // Discord doesn't render the unicode isolation marks.
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(res)
+                    .unwrap_or_else(|errs| panic!("invalid {} catalog: {:?}", tag, errs));
+
+                (*tag, bundle)
+            })
+            .collect();
+
+        I18n {
+            bundles,
+            guilds: RwLock::default(),
+        }
+    }
+
+    pub fn languages(&self) -> Vec<&'static str> {
+        CATALOGS.iter().map(|(tag, _)| *tag).collect()
+    }
+
+    /// Finds a supported language matching `tag`. An exact match wins, then
+    /// a language with the same primary subtag, e.g. `en` for `en-US`.
+    pub fn find_language(&self, tag: &str) -> Option<&'static str> {
+        let want: LanguageIdentifier = tag.parse().ok()?;
+        let langs = self.languages();
+
+        langs
+            .iter()
+            .find(|l| l.eq_ignore_ascii_case(tag))
+            .or_else(|| {
+                langs.iter().find(|l| {
+                    l.parse::<LanguageIdentifier>()
+                        .map(|l| l.language == want.language)
+                        .unwrap_or(false)
+                })
+            })
+            .copied()
+    }
+
+    pub fn set_guild_language(&self, gid: GuildId, lang: &'static str) {
+        self.guilds.write().unwrap().insert(gid, lang);
+    }
+
+    /// Makes `gid` use the default language again.
+    pub fn clear_guild_language(&self, gid: GuildId) {
+        self.guilds.write().unwrap().remove(&gid);
+    }
+
+    /// Returns the locale to use in `gid`, or the default outside of guilds.
+    pub fn locale(&self, gid: Option<GuildId>) -> Locale<'_> {
+        let lang = gid
+            .and_then(|gid| self.guilds.read().unwrap().get(&gid).copied())
+            .unwrap_or(DEFAULT_LANGUAGE);
+
+        Locale { i18n: self, lang }
+    }
+
+    fn format(&self, lang: &str, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = self.bundles.get(lang)?;
+        let pattern = bundle.get_message(key)?.value?;
+
+        let mut errors = Vec::new();
+        let out = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            println!("format message {} ({}): {:?}", key, lang, errors);
+        }
+
+        Some(out.into_owned())
+    }
+}
+
+impl Default for I18n {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A language to render messages in.
+#[derive(Clone, Copy)]
+pub struct Locale<'a> {
+    i18n: &'a I18n,
+    lang: &'static str,
+}
+
+impl<'a> Locale<'a> {
+    pub fn lang(&self) -> &'static str {
+        self.lang
+    }
+
+    /// Formats the message `key`, falling back to the default language, and
+    /// then to the key itself.
+    pub fn tr(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        self.get(key, args).unwrap_or_else(|| key.to_owned())
+    }
+
+    /// Like [`Locale::tr`], but returns `None` for messages that aren't in
+    /// any catalog.
+    pub fn get(&self, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.i18n
+            .format(self.lang, key, args)
+            .or_else(|| self.i18n.format(DEFAULT_LANGUAGE, key, args))
+    }
+
+    /// Formats an integer with the language's digit grouping, e.g. `12,345`.
+    pub fn number(&self, n: i64) -> String {
+        let sep = self.tr("fmt-number-group", None);
+        let digits = n.abs().to_string();
+
+        let mut out = String::new();
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                out.push_str(&sep);
+            }
+            out.push(c);
+        }
+
+        if n < 0 {
+            format!("-{}", out)
+        } else {
+            out
+        }
+    }
+
+    pub fn date(&self, dt: &DateTime<Utc>) -> String {
+        dt.format(&self.tr("fmt-date", None)).to_string()
+    }
+
+    /// Describes how long ago `dt` was, e.g. `3 hours ago`.
+    pub fn relative(&self, dt: &DateTime<Utc>) -> String {
+        let secs = Utc::now().signed_duration_since(*dt).num_seconds().max(0);
+
+        let (key, count) = match secs {
+            0..=9 => return tr!(self, "time-just-now"),
+            10..=59 => ("time-seconds-ago", secs),
+            60..=3599 => ("time-minutes-ago", secs / 60),
+            3600..=86399 => ("time-hours-ago", secs / 3600),
+            86400..=2_591_999 => ("time-days-ago", secs / 86400),
+            2_592_000..=31_535_999 => ("time-months-ago", secs / 2_592_000),
+            _ => ("time-years-ago", secs / 31_536_000),
+        };
+
+        tr!(self, key, count = count)
+    }
+}
@@ -1,9 +1,33 @@
 #![feature(poll_map)]
 
+#[macro_use]
+mod i18n;
+mod audit;
+mod bus;
 pub mod commands;
+mod config;
 mod error;
+mod extract;
+mod format;
 mod help;
+mod limits;
 mod parse;
+mod permissions;
+mod presence;
+pub mod reference;
 mod rikka;
+mod scheduler;
+mod settings;
+pub mod storage;
+mod subcommand;
+mod suggest;
+mod webhooks;
 
-pub use rikka::Rikka;
+pub use bus::{BotEvent, BotEventKind, ModerationAction, ModerationKind, SettingChanged};
+pub use config::Config;
+pub use error::{CommandError, CommandResult};
+pub use help::{CommandHelp, HelpSection};
+pub use limits::LimiterStats;
+pub use permissions::Requirement;
+pub use rikka::{Command, LatencyProbe, Rikka};
+pub use scheduler::{Job, JobId};
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard, Semaphore};
+
+/// Maximum number of handler tasks running across every command.
+pub const DEFAULT_GLOBAL_LIMIT: usize = 256;
+/// Maximum number of handler tasks running for a single command.
+pub const DEFAULT_COMMAND_LIMIT: usize = 32;
+/// Maximum number of handler tasks waiting in line for a single command,
+/// see [`Limiter::enqueue`].
+pub const QUEUE_LIMIT: usize = 64;
+
+/// A counting semaphore that keeps track of how many tasks are waiting on it
+/// and how many are currently holding a permit.
+pub struct Limiter {
+    sem: Semaphore,
+    /// Places in line for `sem`, handed out by [`Limiter::enqueue`].
+    line: Semaphore,
+    limit: usize,
+
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    completed: AtomicU64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LimiterStats {
+    pub limit: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub completed: u64,
+}
+
+/// A slot in a [`Limiter`], released when dropped. Owns a handle to the
+/// limiter so it can be moved into spawned tasks.
+pub struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+/// A place in line for a [`Permit`], released when dropped.
+pub struct Ticket {
+    limiter: Arc<Limiter>,
+}
+
+impl Limiter {
+    pub fn new(limit: usize) -> Self {
+        Limiter {
+            sem: Semaphore::new(limit),
+            line: Semaphore::new(QUEUE_LIMIT),
+            limit,
+
+            queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn acquire(self: Arc<Self>) -> Permit {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        // The permit is handed back in `Permit`'s drop.
+        self.sem.acquire().await.forget();
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+        self.running.fetch_add(1, Ordering::Relaxed);
+
+        Permit { limiter: self }
+    }
+
+    /// Takes a slot if one is free right now.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        self.sem.try_acquire().ok()?.forget();
+        self.running.fetch_add(1, Ordering::Relaxed);
+
+        Some(Permit {
+            limiter: self.clone(),
+        })
+    }
+
+    /// Takes a place in line for a slot, waiting if [`QUEUE_LIMIT`] tasks are
+    /// already in line. Unlike [`Limiter::acquire`], this is bounded, so
+    /// callers that can't wait for a slot themselves still can't pile up
+    /// tasks without limit.
+    pub async fn enqueue(self: Arc<Self>) -> Ticket {
+        self.line.acquire().await.forget();
+        self.queued.fetch_add(1, Ordering::Relaxed);
+
+        Ticket { limiter: self }
+    }
+
+    pub fn stats(&self) -> LimiterStats {
+        LimiterStats {
+            limit: self.limit,
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.limiter.sem.add_permits(1);
+        self.limiter.running.fetch_sub(1, Ordering::Relaxed);
+        self.limiter.completed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Ticket {
+    /// Waits for the slot this ticket is in line for.
+    pub async fn acquire(self) -> Permit {
+        self.limiter.sem.acquire().await.forget();
+        self.limiter.running.fetch_add(1, Ordering::Relaxed);
+
+        Permit {
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.limiter.line.add_permits(1);
+        self.limiter.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// One lock per key, so work like creating a channel's webhook happens once
+/// at a time per key. A key's lock is dropped once nobody holds or waits for
+/// it.
+pub struct KeyedLocks<K> {
+    locks: Mutex<HashMap<K, Arc<AsyncMutex<()>>>>,
+}
+
+/// Holds a key's lock in [`KeyedLocks`] until dropped.
+pub struct KeyedGuard<'a, K: Eq + Hash> {
+    locks: &'a KeyedLocks<K>,
+    key: K,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl<K> Default for KeyedLocks<K> {
+    fn default() -> Self {
+        KeyedLocks {
+            locks: Mutex::default(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> KeyedLocks<K> {
+    pub async fn lock(&self, key: K) -> KeyedGuard<'_, K> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(AsyncMutex::new(())))
+            .clone();
+
+        KeyedGuard {
+            locks: self,
+            key,
+            _guard: lock.lock_owned().await,
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.locks.lock().unwrap().len()
+    }
+}
+
+impl<K: Eq + Hash> Drop for KeyedGuard<'_, K> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap();
+        // The map and this guard hold the only references, so nobody else
+        // is waiting for it.
+        if locks
+            .get(&self.key)
+            .map_or(false, |l| Arc::strong_count(l) == 2)
+        {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tickets_wait_in_a_bounded_line() {
+        let limiter = Arc::new(Limiter::new(1));
+        let running = limiter.try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+
+        let mut tickets = Vec::new();
+        for _ in 0..QUEUE_LIMIT {
+            tickets.push(limiter.clone().enqueue().await);
+        }
+        assert_eq!(limiter.stats().queued, QUEUE_LIMIT);
+
+        let full = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            limiter.clone().enqueue(),
+        );
+        assert!(full.await.is_err());
+
+        drop(running);
+        let permit = tickets.remove(0).acquire().await;
+        let stats = limiter.stats();
+        assert_eq!((stats.queued, stats.running), (QUEUE_LIMIT - 1, 1));
+        drop(permit);
+        assert_eq!(limiter.stats().completed, 2);
+    }
+
+    #[tokio::test]
+    async fn keyed_locks_are_removed_when_released() {
+        let locks = Arc::new(KeyedLocks::default());
+        let first = locks.lock(1).await;
+        let other = locks.lock(2).await;
+        assert_eq!(locks.len(), 2);
+
+        let waiter = locks.clone();
+        let waiting = tokio::spawn(async move {
+            waiter.lock(1).await;
+        });
+        tokio::task::yield_now().await;
+        drop(first);
+        drop(other);
+
+        waiting.await.unwrap();
+        assert_eq!(locks.len(), 0);
+    }
+}
@@ -1,26 +1,495 @@
 use super::Rikka;
-use crate::error::CommandError;
-use twilight_command_parser::{Arguments, Command};
+use crate::error::{ArgError, CommandError, Expected};
+use crate::extract::Extract;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use twilight_command_parser::Command;
 use twilight_model::channel::Message;
 
 pub fn matches_command<'a>(
     bot: &'a Rikka,
     msg: &'a Message,
     cmds: &[&'static str],
-) -> Result<Arguments<'a>, CommandError> {
+) -> Result<Args<'a>, CommandError> {
     if msg.author.bot {
         return Err(CommandError::NoMatch);
     }
 
-    let found = bot
-        .parser
-        .parse(&msg.content)
-        .ok_or(CommandError::NoMatch)?;
+    let parser = bot.parser.read().unwrap();
+    let found = parser.parse(&msg.content).ok_or(CommandError::NoMatch)?;
 
     match found {
         Command {
             name, arguments, ..
-        } if cmds.contains(&name) => Ok(arguments),
+        } if cmds.contains(&name) => {
+            // The arguments borrow from the parser's lock, so find them in
+            // the message instead. They're always a suffix of it.
+            let rest = arguments.as_str();
+            let buf = &msg.content[msg.content.len() - rest.len()..];
+
+            Ok(Args::new(bot, msg, buf))
+        }
         _ => Err(CommandError::NoMatch),
     }
 }
+
+/// The arguments following a command's name, along with the context needed
+/// to resolve them into typed values with [`Args::parse`].
+///
+/// Arguments are split on whitespace. Quoting with `"` or `“”` keeps
+/// whitespace together, and inside quotes `\` escapes the next character.
+/// Unquoted arguments starting with `--` or a single `-` and a letter can be
+/// pulled out as flags and options; everything after a bare `--` is
+/// positional. Flags and options should be extracted before positional
+/// arguments so their values aren't mistaken for positionals.
+#[derive(Clone)]
+pub struct Args<'a> {
+    pub(crate) bot: &'a Rikka,
+    pub(crate) msg: &'a Message,
+
+    tokens: Tokens<'a>,
+}
+
+impl<'a> Args<'a> {
+    pub fn new(bot: &'a Rikka, msg: &'a Message, buf: &'a str) -> Self {
+        Args {
+            bot,
+            msg,
+            tokens: Tokens::new(buf),
+        }
+    }
+
+    /// Parses the next argument(s) as `T`. `name` is used in error messages.
+    pub fn parse<T: Extract<'a>>(&mut self, name: &str) -> Result<T, ArgError> {
+        T::extract(self, name)
+    }
+
+    /// Like [`Args::parse`], but also rejects values outside of `range`.
+    pub fn parse_in<T>(&mut self, name: &str, range: RangeInclusive<T>) -> Result<T, ArgError>
+    where
+        T: Extract<'a> + PartialOrd + Display,
+    {
+        let raw = self.clone().next().unwrap_or_default();
+        let val = self.parse::<T>(name)?;
+
+        if !range.contains(&val) {
+            return Err(ArgError::Invalid {
+                usage: T::usage(name),
+                expected: Expected::Range(range.start().to_string(), range.end().to_string()),
+                value: raw.into_owned(),
+            });
+        }
+
+        Ok(val)
+    }
+
+    /// Consumes `--name`, or `-n` for single character names, and returns
+    /// whether it was present.
+    pub fn flag(&mut self, name: &str) -> bool {
+        self.tokens.flag(name)
+    }
+
+    /// Consumes `--name value`, `--name=value` or `-n value` and parses the
+    /// value as `T`.
+    pub fn option<T: Extract<'a>>(&mut self, name: &str) -> Result<Option<T>, ArgError> {
+        let value = match self.tokens.option(name) {
+            Some(Some(value)) => value,
+            Some(None) => {
+                return Err(ArgError::Missing(format!(
+                    "{} {}",
+                    fmt_flag(name),
+                    T::usage(name)
+                )))
+            }
+            None => return Ok(None),
+        };
+
+        let mut args = Args {
+            bot: self.bot,
+            msg: self.msg,
+            tokens: Tokens::single(value),
+        };
+
+        T::extract(&mut args, name).map(Some)
+    }
+
+    /// Returns everything that hasn't been consumed yet as it was typed, and
+    /// consumes it.
+    pub fn rest(&mut self) -> Cow<'a, str> {
+        self.tokens.rest()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokens.next()
+    }
+}
+
+/// Lexed arguments, and which of them were consumed.
+#[derive(Clone, Debug)]
+struct Tokens<'a> {
+    buf: &'a str,
+    list: Vec<Token<'a>>,
+    /// Index of the first token after a bare `--`.
+    flags_end: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Token<'a> {
+    /// The argument with quotes and escapes resolved.
+    text: Cow<'a, str>,
+    /// Where the argument, as it was typed, starts and ends in the buffer.
+    start: usize,
+    end: usize,
+    quoted: bool,
+    used: bool,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(buf: &'a str) -> Self {
+        let mut list = tokenize(buf);
+        let flags_end = list
+            .iter()
+            .position(|t| !t.quoted && &buf[t.start..t.end] == "--")
+            .map(|idx| {
+                list[idx].used = true;
+                idx
+            })
+            .unwrap_or_else(|| list.len());
+
+        Tokens {
+            buf,
+            list,
+            flags_end,
+        }
+    }
+
+    /// A single argument that's taken as is, e.g. an option's value.
+    fn single(text: Cow<'a, str>) -> Self {
+        Tokens {
+            buf: "",
+            list: vec![Token {
+                text,
+                start: 0,
+                end: 0,
+                quoted: true,
+                used: false,
+            }],
+            flags_end: 0,
+        }
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        match self.find_flag(name) {
+            Some(idx) if self.list[idx].text == fmt_flag(name) => {
+                self.list[idx].used = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consumes the option `name` and its value. Returns `Some(None)` if the
+    /// option is there but its value isn't.
+    fn option(&mut self, name: &str) -> Option<Option<Cow<'a, str>>> {
+        let idx = self.find_flag(name)?;
+        self.list[idx].used = true;
+
+        let flag = fmt_flag(name);
+        let value = match &self.list[idx].text {
+            Cow::Borrowed(text) if text.len() > flag.len() => {
+                Cow::Borrowed(&text[flag.len() + 1..])
+            }
+            Cow::Owned(text) if text.len() > flag.len() => {
+                Cow::Owned(text[flag.len() + 1..].to_owned())
+            }
+            // Only the argument right after the option can be its value,
+            // and never one after a bare `--`.
+            _ => match self.list.get_mut(idx + 1) {
+                Some(tok) if idx + 1 < self.flags_end && !tok.used => {
+                    tok.used = true;
+                    tok.text.clone()
+                }
+                _ => return Some(None),
+            },
+        };
+
+        Some(Some(value))
+    }
+
+    fn find_flag(&self, name: &str) -> Option<usize> {
+        let flag = fmt_flag(name);
+        let with_value = format!("{}=", flag);
+
+        self.list[..self.flags_end].iter().position(|t| {
+            !t.used && !t.quoted && (t.text == flag || t.text.starts_with(&with_value))
+        })
+    }
+
+    fn rest(&mut self) -> Cow<'a, str> {
+        let first = match self.list.iter().position(|t| !t.used) {
+            Some(first) => first,
+            None => return Cow::Borrowed(""),
+        };
+
+        let rest = &mut self.list[first..];
+        let text = if rest.iter().all(|t| !t.used) {
+            // Keep the original spacing between arguments.
+            Cow::Borrowed(&self.buf[rest[0].start..rest[rest.len() - 1].end])
+        } else {
+            // Leave out what was consumed, but keep the whitespace around it
+            // so line breaks survive.
+            let buf = self.buf;
+            let mut text = String::new();
+            let mut prev_end = None;
+            // Where the consumed tokens since the previous kept one start
+            // and end.
+            let mut skipped: Option<(usize, usize)> = None;
+            for tok in rest.iter() {
+                if tok.used {
+                    let start = skipped.map_or(tok.start, |(start, _)| start);
+                    skipped = Some((start, tok.end));
+                    continue;
+                }
+
+                if let Some(prev_end) = prev_end {
+                    let gap = match skipped {
+                        Some((start, end)) => {
+                            let (before, after) = (&buf[prev_end..start], &buf[end..tok.start]);
+                            if after.contains('\n') {
+                                after
+                            } else {
+                                before
+                            }
+                        }
+                        None => &buf[prev_end..tok.start],
+                    };
+                    text.push_str(if gap.is_empty() { " " } else { gap });
+                }
+                text.push_str(&buf[tok.start..tok.end]);
+                prev_end = Some(tok.end);
+                skipped = None;
+            }
+
+            Cow::Owned(text)
+        };
+
+        for tok in rest.iter_mut() {
+            tok.used = true;
+        }
+
+        text
+    }
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        let tok = self.list.iter_mut().find(|t| !t.used)?;
+        tok.used = true;
+
+        Some(tok.text.clone())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.list.iter().all(|t| t.used)
+    }
+}
+
+/// Formats `name` as it's typed, e.g. `--limit` or `-l`.
+pub fn fmt_flag(name: &str) -> String {
+    if name.chars().count() == 1 {
+        format!("-{}", name)
+    } else {
+        format!("--{}", name)
+    }
+}
+
+fn closing_quote(open: char) -> Option<char> {
+    match open {
+        '"' => Some('"'),
+        '“' => Some('”'),
+        _ => None,
+    }
+}
+
+fn tokenize(buf: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let rest = &buf[pos..];
+        let start = pos + (rest.len() - rest.trim_start().len());
+        if start >= buf.len() {
+            break;
+        }
+
+        let (tok, end) = lex_quoted(buf, start).unwrap_or_else(|| lex_word(buf, start));
+        tokens.push(tok);
+        pos = end;
+    }
+
+    tokens
+}
+
+/// Lexes an unquoted argument starting at `start`, up to the next
+/// whitespace. Unquoted arguments are taken as typed, so paths and
+/// emoticons keep their backslashes.
+fn lex_word(buf: &str, start: usize) -> (Token<'_>, usize) {
+    let end = buf[start..]
+        .find(char::is_whitespace)
+        .map_or(buf.len(), |i| start + i);
+
+    (
+        Token {
+            text: Cow::Borrowed(&buf[start..end]),
+            start,
+            end,
+            quoted: false,
+            used: false,
+        },
+        end,
+    )
+}
+
+/// Lexes a quoted argument starting at `start`. Returns `None` if the
+/// argument doesn't start with a quote or the quote is never closed, in which
+/// case it should be lexed as a plain word.
+fn lex_quoted(buf: &str, start: usize) -> Option<(Token<'_>, usize)> {
+    let mut chars = buf[start..].char_indices();
+    let (_, open) = chars.next()?;
+    let close = closing_quote(open)?;
+
+    let mut text = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, next)) = chars.next() {
+                    text.push(next);
+                }
+            }
+            c if c == close => {
+                let end = start + i + c.len_utf8();
+                return Some((
+                    Token {
+                        text: Cow::Owned(text),
+                        start,
+                        end,
+                        quoted: true,
+                        used: false,
+                    },
+                    end,
+                ));
+            }
+            c => text.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(buf: &str) -> Vec<String> {
+        tokenize(buf)
+            .into_iter()
+            .map(|t| t.text.into_owned())
+            .collect()
+    }
+
+    fn remaining(mut tokens: Tokens<'_>) -> Vec<String> {
+        std::iter::from_fn(|| tokens.next())
+            .map(Cow::into_owned)
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(texts("a  b\nc\t d"), ["a", "b", "c", "d"]);
+        assert!(texts("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_keep_whitespace_together() {
+        assert_eq!(texts(r#""a b" “c d” e"#), ["a b", "c d", "e"]);
+        assert_eq!(texts(r#""say \"hi\"""#), [r#"say "hi""#]);
+        assert_eq!(texts(r#""""#), [""]);
+    }
+
+    #[test]
+    fn unclosed_quotes_are_words() {
+        assert_eq!(texts(r#""a b"#), [r#""a"#, "b"]);
+    }
+
+    #[test]
+    fn apostrophes_are_not_quotes() {
+        assert_eq!(texts("'cause it's fine'"), ["'cause", "it's", "fine'"]);
+    }
+
+    #[test]
+    fn backslashes_are_kept_in_words() {
+        assert_eq!(
+            texts(r"C:\Users\me ¯\_(ツ)_/¯"),
+            [r"C:\Users\me", r"¯\_(ツ)_/¯"]
+        );
+    }
+
+    #[test]
+    fn flags_are_pulled_out() {
+        let mut tokens = Tokens::new("a --delete b -d");
+        assert!(tokens.flag("delete"));
+        assert!(!tokens.flag("delete"));
+        assert!(tokens.flag("d"));
+        assert!(!tokens.flag("embed"));
+        assert_eq!(remaining(tokens), ["a", "b"]);
+    }
+
+    #[test]
+    fn quoted_and_late_flags_are_positional() {
+        let mut tokens = Tokens::new(r#""--delete" -- --delete"#);
+        assert!(!tokens.flag("delete"));
+        assert_eq!(remaining(tokens), ["--delete", "--delete"]);
+    }
+
+    #[test]
+    fn options_take_the_next_argument() {
+        let mut tokens = Tokens::new("a --limit 5 b --sort=name");
+        assert_eq!(tokens.option("limit"), Some(Some("5".into())));
+        assert_eq!(tokens.option("sort"), Some(Some("name".into())));
+        assert_eq!(tokens.option("page"), None);
+        assert_eq!(remaining(tokens), ["a", "b"]);
+    }
+
+    #[test]
+    fn options_only_take_an_adjacent_value() {
+        let mut tokens = Tokens::new("--page --limit 5");
+        assert_eq!(tokens.option("limit"), Some(Some("5".into())));
+        assert_eq!(tokens.option("page"), Some(None));
+
+        let mut tokens = Tokens::new("--limit -- 5");
+        assert_eq!(tokens.option("limit"), Some(None));
+        assert_eq!(remaining(tokens), ["5"]);
+    }
+
+    #[test]
+    fn rest_keeps_the_original_text() {
+        let mut tokens = Tokens::new("a  \"b c\"\n d");
+        assert_eq!(tokens.rest(), "a  \"b c\"\n d");
+        assert!(tokens.is_empty());
+
+        let mut tokens = Tokens::new("title: Hi --delete\ndescription: there");
+        assert!(tokens.flag("delete"));
+        assert_eq!(tokens.rest(), "title: Hi\ndescription: there");
+
+        let mut tokens = Tokens::new("hi --delete there");
+        assert!(tokens.flag("delete"));
+        assert_eq!(tokens.rest(), "hi there");
+    }
+}
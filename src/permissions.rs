@@ -0,0 +1,186 @@
+use twilight_model::channel::permission_overwrite::{PermissionOverwrite, PermissionOverwriteType};
+use twilight_model::channel::{GuildChannel, Message};
+use twilight_model::guild::Permissions;
+use twilight_model::id::{ChannelId, GuildId, RoleId, UserId};
+
+use crate::error::CommandError;
+use crate::i18n::Locale;
+use crate::rikka::Rikka;
+
+/// Something a user has to satisfy before they can run a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Requirement {
+    /// Only bot owners.
+    Owner,
+    /// Only in guilds, not DMs.
+    Guild,
+    /// Only in DMs, not guilds.
+    Dm,
+    /// The user needs all of these permissions in the channel.
+    Permissions(Permissions),
+}
+
+impl Requirement {
+    /// Returns why `msg`'s author doesn't meet the requirement, if they
+    /// don't.
+    pub fn check(&self, bot: &Rikka, msg: &Message) -> Option<String> {
+        let loc = bot.locale(msg.guild_id);
+
+        match self {
+            Requirement::Owner if !bot.is_owner(msg.author.id) => Some(tr!(loc, "requires-owner")),
+            Requirement::Guild if msg.guild_id.is_none() => Some(tr!(loc, "requires-guild")),
+            Requirement::Dm if msg.guild_id.is_some() => Some(tr!(loc, "requires-dm")),
+            Requirement::Permissions(perms) => {
+                let gid = match msg.guild_id {
+                    Some(gid) => gid,
+                    None => return Some(tr!(loc, "requires-guild")),
+                };
+
+                let missing = *perms - channel_permissions(bot, gid, msg.channel_id, msg.author.id);
+                if missing.is_empty() || bot.is_owner(msg.author.id) {
+                    None
+                } else {
+                    Some(tr!(
+                        loc,
+                        "requires-permissions",
+                        permissions = fmt_permissions(missing)
+                    ))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `msg`'s author could meet the requirement somewhere, even if
+    /// not where `msg` was sent.
+    pub fn attainable(&self, bot: &Rikka, msg: &Message) -> bool {
+        match self {
+            Requirement::Owner => bot.is_owner(msg.author.id),
+            // Anyone can DM the bot.
+            Requirement::Dm => true,
+            _ => true,
+        }
+    }
+
+    /// A short human readable description, for help pages.
+    pub fn describe(&self, loc: &Locale) -> String {
+        match self {
+            Requirement::Owner => tr!(loc, "requirement-owner"),
+            Requirement::Guild => tr!(loc, "requirement-guild"),
+            Requirement::Dm => tr!(loc, "requirement-dm"),
+            Requirement::Permissions(perms) => fmt_permissions(*perms),
+        }
+    }
+}
+
+/// Returns the first requirement in `reqs` that `msg`'s author fails, as a
+/// user facing reason.
+pub fn check_all(bot: &Rikka, msg: &Message, reqs: &[Requirement]) -> Option<String> {
+    reqs.iter().find_map(|req| req.check(bot, msg))
+}
+
+/// Like [`check_all`], but fails with the reason so the command is recorded
+/// as denied.
+pub fn require_all(bot: &Rikka, msg: &Message, reqs: &[Requirement]) -> Result<(), CommandError> {
+    match check_all(bot, msg, reqs) {
+        Some(reason) => Err(CommandError::Denied(reason)),
+        None => Ok(()),
+    }
+}
+
+/// Computes a member's effective permissions in a channel from the cache.
+/// Unknown members, roles or channels are treated as granting nothing.
+pub fn channel_permissions(bot: &Rikka, gid: GuildId, cid: ChannelId, uid: UserId) -> Permissions {
+    let guild = match bot.cache.guild(gid) {
+        Some(guild) => guild,
+        None => return Permissions::empty(),
+    };
+    if guild.owner_id == uid {
+        return Permissions::all();
+    }
+
+    let roles = bot
+        .cache
+        .member(gid, uid)
+        .map(|m| m.roles.clone())
+        .unwrap_or_default();
+
+    // The @everyone role shares its id with the guild.
+    let mut perms = role_permissions(bot, RoleId(gid.0));
+    for rid in roles.iter() {
+        perms |= role_permissions(bot, *rid);
+    }
+
+    if perms.contains(Permissions::ADMINISTRATOR) {
+        return Permissions::all();
+    }
+
+    let overwrites = match bot.cache.guild_channel(cid) {
+        Some(ch) => channel_overwrites(&ch).to_vec(),
+        None => return perms,
+    };
+
+    let apply = |perms: Permissions, ow: &PermissionOverwrite| (perms - ow.deny) | ow.allow;
+
+    if let Some(ow) = overwrites
+        .iter()
+        .find(|ow| ow.kind == PermissionOverwriteType::Role(RoleId(gid.0)))
+    {
+        perms = apply(perms, ow);
+    }
+
+    let (mut allow, mut deny) = (Permissions::empty(), Permissions::empty());
+    for ow in overwrites.iter() {
+        if let PermissionOverwriteType::Role(rid) = ow.kind {
+            if rid.0 != gid.0 && roles.contains(&rid) {
+                allow |= ow.allow;
+                deny |= ow.deny;
+            }
+        }
+    }
+    perms = (perms - deny) | allow;
+
+    if let Some(ow) = overwrites
+        .iter()
+        .find(|ow| ow.kind == PermissionOverwriteType::Member(uid))
+    {
+        perms = apply(perms, ow);
+    }
+
+    perms
+}
+
+fn role_permissions(bot: &Rikka, rid: RoleId) -> Permissions {
+    bot.cache
+        .role(rid)
+        .map(|role| role.permissions)
+        .unwrap_or_else(Permissions::empty)
+}
+
+fn channel_overwrites(ch: &GuildChannel) -> &[PermissionOverwrite] {
+    match ch {
+        GuildChannel::Category(c) => &c.permission_overwrites,
+        GuildChannel::Text(c) => &c.permission_overwrites,
+        GuildChannel::Voice(c) => &c.permission_overwrites,
+    }
+}
+
+/// Formats permission flags as e.g. `Manage Guild, Ban Members`.
+pub fn fmt_permissions(perms: Permissions) -> String {
+    format!("{:?}", perms)
+        .split(" | ")
+        .map(|flag| {
+            flag.split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,247 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use strum_macros::{EnumString, EnumVariantNames};
+use twilight_model::gateway::payload::update_status::UpdateStatus;
+use twilight_model::gateway::presence::{Activity, ActivityType, Status};
+
+use crate::rikka::Rikka;
+
+/// The presences rotated through by default. See [`render`] for the
+/// variables they can use.
+pub const DEFAULT_TEMPLATES: &[&str] = &[
+    "{prefix}help | {guilds} servers",
+    "{prefix}help | {users} users",
+];
+/// How long each presence is shown by default.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The shortest time a presence is shown, so rotating doesn't run into the
+/// gateway's rate limits.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The statuses an owner can pick, as they're typed.
+#[derive(EnumString, EnumVariantNames, Clone, Copy, Debug, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum PresenceStatus {
+    Online,
+    Idle,
+    Dnd,
+    Invisible,
+}
+
+impl From<PresenceStatus> for Status {
+    fn from(status: PresenceStatus) -> Self {
+        match status {
+            PresenceStatus::Online => Status::Online,
+            PresenceStatus::Idle => Status::Idle,
+            PresenceStatus::Dnd => Status::DoNotDisturb,
+            PresenceStatus::Invisible => Status::Invisible,
+        }
+    }
+}
+
+/// The kinds of activity a bot can show. Streaming needs a URL, so it's left
+/// out.
+#[derive(EnumString, EnumVariantNames, Clone, Copy, Debug, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ActivityKind {
+    Playing,
+    Listening,
+    Watching,
+}
+
+impl From<ActivityKind> for ActivityType {
+    fn from(kind: ActivityKind) -> Self {
+        match kind {
+            ActivityKind::Playing => ActivityType::Playing,
+            ActivityKind::Listening => ActivityType::Listening,
+            ActivityKind::Watching => ActivityType::Watching,
+        }
+    }
+}
+
+/// An activity showing just `name`, e.g. "Playing `name`".
+pub fn activity(kind: ActivityKind, name: String) -> Activity {
+    Activity {
+        application_id: None,
+        assets: None,
+        created_at: None,
+        details: None,
+        emoji: None,
+        flags: None,
+        id: None,
+        instance: None,
+        kind: kind.into(),
+        name,
+        party: None,
+        secrets: None,
+        state: None,
+        timestamps: None,
+        url: None,
+    }
+}
+
+/// Sets the bot's presence on every shard. Returns how many shards were
+/// updated, which leaves out shards that aren't connected.
+pub async fn set_presence(
+    bot: &Rikka,
+    status: PresenceStatus,
+    activity: Option<Activity>,
+) -> usize {
+    let mut updated = 0;
+    for id in bot.cluster.info().keys() {
+        if set_shard_presence(bot, *id, status, activity.clone()).await {
+            updated += 1;
+        }
+    }
+
+    updated
+}
+
+/// Sets the bot's presence on a single shard, returning whether it worked.
+async fn set_shard_presence(
+    bot: &Rikka,
+    id: u64,
+    status: PresenceStatus,
+    activity: Option<Activity>,
+) -> bool {
+    let shard = match bot.cluster.shard(id) {
+        Some(shard) => shard,
+        None => return false,
+    };
+
+    let update = UpdateStatus::new(false, activity, None, Status::from(status));
+    match shard.command(&update).await {
+        Ok(()) => true,
+        Err(err) => {
+            println!("set presence on shard {}: {}", id, err);
+            false
+        }
+    }
+}
+
+/// Presence templates the bot cycles through, unless an owner set one by
+/// hand.
+pub struct Presences {
+    templates: RwLock<Vec<String>>,
+    interval: RwLock<Duration>,
+    next: AtomicUsize,
+    paused: AtomicBool,
+}
+
+impl Presences {
+    pub fn new(templates: Vec<String>, interval: Duration) -> Self {
+        Presences {
+            templates: RwLock::new(templates),
+            interval: RwLock::new(interval.max(MIN_INTERVAL)),
+            next: AtomicUsize::new(0),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    /// Replaces the templates, starting over from the first. No templates
+    /// leaves the presence alone.
+    pub fn set_templates(&self, templates: Vec<String>) {
+        *self.templates.write().unwrap() = templates;
+        self.next.store(0, Ordering::Relaxed);
+    }
+
+    /// Sets how long each presence is shown, which is at least
+    /// [`MIN_INTERVAL`]. Takes effect after the current one.
+    pub fn set_interval(&self, interval: Duration) {
+        *self.interval.write().unwrap() = interval.max(MIN_INTERVAL);
+    }
+
+    pub fn interval(&self) -> Duration {
+        *self.interval.read().unwrap()
+    }
+
+    /// The template shown now, if there are any.
+    fn current(&self) -> Option<String> {
+        let templates = self.templates.read().unwrap();
+        if templates.is_empty() {
+            return None;
+        }
+
+        let idx = self.next.load(Ordering::Relaxed) % templates.len();
+        Some(templates[idx].clone())
+    }
+
+    fn advance(&self) {
+        self.next.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stops rotating, e.g. because an owner set a presence by hand.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// Fills in a presence template. `{prefix}` is the command prefix, `{guilds}`,
+/// `{channels}` and `{users}` are counted from the cache and `{shards}` is how
+/// many shards the bot runs.
+pub fn render(bot: &Rikka, template: &str) -> String {
+    let mut out = template
+        .replace("{prefix}", &bot.prefix)
+        .replace("{guilds}", &bot.guild_ids().len().to_string())
+        .replace("{shards}", &bot.cluster.info().len().to_string());
+
+    // Counting users means going through every member, so only do it when
+    // it's needed.
+    if out.contains("{channels}") || out.contains("{users}") {
+        let (channels, users) = bot.cached_counts();
+        out = out
+            .replace("{channels}", &channels.to_string())
+            .replace("{users}", &users.to_string());
+    }
+
+    out
+}
+
+/// Shows the current template on every shard, unless rotation is paused.
+/// Returns what was shown.
+pub async fn apply(bot: &Rikka) -> Option<String> {
+    let name = current(bot)?;
+    let shown = activity(ActivityKind::Playing, name.clone());
+    set_presence(bot, PresenceStatus::Online, Some(shown)).await;
+
+    Some(name)
+}
+
+/// Shows the current template on the shard `id`, e.g. because it just
+/// connected.
+pub async fn apply_to_shard(bot: &Rikka, id: u64) {
+    if let Some(name) = current(bot) {
+        let shown = activity(ActivityKind::Playing, name);
+        set_shard_presence(bot, id, PresenceStatus::Online, Some(shown)).await;
+    }
+}
+
+/// The current template filled in, unless rotation is paused.
+fn current(bot: &Rikka) -> Option<String> {
+    if bot.presences.is_paused() {
+        return None;
+    }
+
+    Some(render(bot, &bot.presences.current()?))
+}
+
+/// Moves on to the next template every interval, for as long as the bot runs.
+/// Templates can be added later, so this keeps going without any.
+pub async fn rotate(bot: Arc<Rikka>) {
+    loop {
+        tokio::time::delay_for(bot.presences.interval()).await;
+        if !bot.presences.is_paused() {
+            bot.presences.advance();
+            apply(&bot).await;
+        }
+    }
+}
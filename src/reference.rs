@@ -0,0 +1,183 @@
+use serde::Serialize;
+use std::sync::Arc;
+use strum::IntoEnumIterator;
+
+use crate::help::{help_description, help_detailed, requirements, CommandHelp, HelpSection};
+use crate::i18n::{I18n, Locale};
+use crate::permissions::Requirement;
+use crate::rikka::Command;
+use crate::subcommand::SubcommandInfo;
+
+/// A reference of every command, built from their [`CommandHelp`] so docs
+/// can be regenerated from code. See `src/bin/reference.rs`.
+#[derive(Serialize)]
+pub struct Reference {
+    pub prefix: String,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Serialize)]
+pub struct Section {
+    pub name: String,
+    pub commands: Vec<Entry>,
+}
+
+/// A command or one of its subcommands.
+#[derive(Serialize)]
+pub struct Entry {
+    /// The full path to the command, e.g. `log message enable`.
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub detailed: String,
+    pub usage: String,
+    pub examples: Vec<String>,
+    /// What's needed to run the command, on top of what its parent needs.
+    pub requires: Vec<String>,
+    pub cooldown_secs: Option<u64>,
+    pub subcommands: Vec<Entry>,
+}
+
+impl Reference {
+    /// Builds the reference for `cmds`, with descriptions of commands,
+    /// sections and requirements in the default language.
+    pub fn new(prefix: &str, cmds: &[Arc<dyn Command>]) -> Self {
+        let i18n = I18n::new();
+        let loc = i18n.locale(None);
+
+        let mut sections = Vec::new();
+        for sect in HelpSection::iter() {
+            let mut commands = Vec::new();
+            for cmd in cmds {
+                for help in cmd.help(None) {
+                    if help.section == sect {
+                        commands.push(command_entry(&loc, &**cmd, &help));
+                    }
+                }
+            }
+
+            if !commands.is_empty() {
+                sections.push(Section {
+                    name: tr!(loc, sect.key()),
+                    commands,
+                });
+            }
+        }
+
+        Reference {
+            prefix: prefix.to_owned(),
+            sections,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Command reference\n");
+        for sect in &self.sections {
+            out.push_str(&format!("\n## {}\n", sect.name));
+            for entry in &sect.commands {
+                self.push_entry(&mut out, entry, 3);
+            }
+        }
+
+        out
+    }
+
+    fn push_entry(&self, out: &mut String, entry: &Entry, depth: usize) {
+        let invocation = format!("{}{}", self.prefix, entry.name);
+        out.push_str(&format!(
+            "\n{} `{}`\n\n",
+            "#".repeat(depth.min(6)),
+            invocation
+        ));
+
+        if !entry.description.is_empty() {
+            out.push_str(&format!("{}\n\n", entry.description));
+        }
+        if !entry.detailed.is_empty() {
+            out.push_str(&format!("{}\n\n", entry.detailed));
+        }
+
+        let usage = format!("{} {}", invocation, entry.usage);
+        out.push_str(&format!("- Usage: `{}`\n", usage.trim_end()));
+        if !entry.aliases.is_empty() {
+            out.push_str(&format!("- Aliases: {}\n", fmt_code(&entry.aliases, "")));
+        }
+        if !entry.requires.is_empty() {
+            out.push_str(&format!("- Requires: {}\n", entry.requires.join(", ")));
+        }
+        if let Some(secs) = entry.cooldown_secs {
+            out.push_str(&format!("- Cooldown: {}s\n", secs));
+        }
+        if !entry.examples.is_empty() {
+            out.push_str(&format!(
+                "- Examples: {}\n",
+                fmt_code(&entry.examples, &self.prefix)
+            ));
+        }
+
+        for sub in &entry.subcommands {
+            self.push_entry(out, sub, depth + 1);
+        }
+    }
+}
+
+fn command_entry(loc: &Locale, cmd: &dyn Command, help: &CommandHelp) -> Entry {
+    let node = cmd.subcommands();
+
+    Entry {
+        name: help.name.to_owned(),
+        aliases: to_owned(help.aliases),
+        description: help_description(loc, help.name),
+        detailed: help_detailed(loc, help.name),
+        usage: help.usage.clone(),
+        examples: to_owned(help.examples),
+        requires: describe(loc, &requirements(cmd, help)),
+        cooldown_secs: help.cooldown.map(|c| c.as_secs()),
+        subcommands: node
+            .map(|n| n.children())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|child| subcommand_entry(loc, help.name, child))
+            .collect(),
+    }
+}
+
+fn subcommand_entry(loc: &Locale, parent: &str, node: &dyn SubcommandInfo) -> Entry {
+    let name = format!("{} {}", parent, node.name());
+
+    Entry {
+        aliases: to_owned(node.aliases()),
+        description: help_description(loc, &name),
+        detailed: help_detailed(loc, &name),
+        usage: node.usage().to_owned(),
+        examples: to_owned(node.examples()),
+        requires: describe(loc, node.requires()),
+        cooldown_secs: None,
+        subcommands: node
+            .children()
+            .into_iter()
+            .map(|child| subcommand_entry(loc, &name, child))
+            .collect(),
+        name,
+    }
+}
+
+fn describe(loc: &Locale, reqs: &[Requirement]) -> Vec<String> {
+    reqs.iter().map(|req| req.describe(loc)).collect()
+}
+
+fn to_owned(strs: &[&str]) -> Vec<String> {
+    strs.iter().map(|s| (*s).to_owned()).collect()
+}
+
+/// Formats each of `strs` as code after `prefix`, e.g. `` `r.ping`, `r.help` ``.
+fn fmt_code(strs: &[String], prefix: &str) -> String {
+    strs.iter()
+        .map(|s| format!("`{}{}`", prefix, s))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
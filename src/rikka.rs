@@ -1,6 +1,14 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::future::BoxFuture;
+use futures::Stream;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::stream::StreamExt;
+use tokio::sync::Notify;
 
 use twilight_cache_inmemory::{EventType, InMemoryCache};
 use twilight_command_parser::{CommandParserConfig, Parser};
@@ -8,10 +16,26 @@ use twilight_gateway::cluster::{Cluster, ShardScheme};
 use twilight_gateway::Event;
 use twilight_http::Client as HttpClient;
 use twilight_model::gateway::payload::request_guild_members::RequestGuildMembersBuilder;
+use twilight_model::id::{GuildId, UserId};
 use twilight_model::{channel::Message, gateway::Intents};
 
+use crate::audit::{self, Invocation, Outcome, DEFAULT_RETENTION_DAYS};
+use crate::bus::{BotEvent, BotEventKind};
+use crate::config::Config;
 use crate::error::{CommandError, CommandResult};
+use crate::format::{truncate, MESSAGE_LIMIT};
 use crate::help::CommandHelp;
+use crate::i18n::{I18n, Locale};
+use crate::limits::{
+    Limiter, LimiterStats, Permit, Ticket, DEFAULT_COMMAND_LIMIT, DEFAULT_GLOBAL_LIMIT,
+};
+use crate::presence::{self, Presences};
+use crate::scheduler::{self, Claimed, Job, JobId, DEFAULT_POLL_INTERVAL};
+use crate::settings;
+use crate::storage::Storage;
+use crate::subcommand::SubcommandInfo;
+use crate::suggest::{closest, Suggestions, DEFAULT_COOLDOWN};
+use crate::webhooks::Webhooks;
 
 #[async_trait]
 pub trait Command: Send + Sync {
@@ -21,6 +45,11 @@ pub trait Command: Send + Sync {
         Vec::default()
     }
 
+    /// The command's subcommand tree, if it has one.
+    fn subcommands(&self) -> Option<&dyn SubcommandInfo> {
+        None
+    }
+
     async fn receive(&self, _: &Rikka, _: &Message) -> CommandResult {
         Ok(None)
     }
@@ -28,74 +57,728 @@ pub trait Command: Send + Sync {
     async fn receive_raw(&self, _: &Rikka, _: &Event) -> Result<(), CommandError> {
         Ok(())
     }
+
+    /// The kinds of [`BotEvent`] passed to [`Command::receive_event`].
+    fn subscribes(&self) -> &'static [BotEventKind] {
+        &[]
+    }
+
+    /// Receives events other commands published with [`Rikka::publish`], if
+    /// they're of a kind in [`Command::subscribes`].
+    async fn receive_event(&self, _: &Rikka, _: &BotEvent) -> Result<(), CommandError> {
+        Ok(())
+    }
+
+    /// The kinds of [`Job`] this command runs.
+    fn job_kinds(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Runs a scheduled job of one of the kinds in [`Command::job_kinds`].
+    /// Failed jobs are retried later.
+    async fn run_job(&self, _: &Rikka, _: &Job) -> Result<(), CommandError> {
+        Ok(())
+    }
+
+    /// Returns a catalog key naming something the command depends on, along
+    /// with a probe measuring how long it takes to respond, for `ping`.
+    fn latency<'a>(&'a self, _: &'a Rikka) -> Option<(&'static str, LatencyProbe<'a>)> {
+        None
+    }
+
+    /// The maximum number of handler tasks that may run for this command at
+    /// once. Tasks over the limit wait in line for a slot, and once the line
+    /// is full the event loop waits too.
+    fn concurrency(&self) -> usize {
+        DEFAULT_COMMAND_LIMIT
+    }
 }
 
-#[derive(Clone)]
+/// Measures the latency of one of a command's dependencies. See
+/// [`Command::latency`].
+pub type LatencyProbe<'a> = BoxFuture<'a, Result<std::time::Duration>>;
+
+/// A registered command along with its runtime state.
+struct Registered {
+    cmd: Arc<dyn Command>,
+    limiter: Arc<Limiter>,
+    enabled: bool,
+}
+
+impl Registered {
+    /// Whether the command is called `name` or has a help entry, or alias,
+    /// called `name`.
+    fn is_named(&self, name: &str) -> bool {
+        self.cmd.name() == name
+            || self
+                .cmd
+                .help(None)
+                .iter()
+                .any(|h| h.name == name || h.aliases.contains(&name))
+    }
+}
+
+/// A handler's slots, see [`Rikka::reserve`].
+enum Reservation {
+    /// The command's slot and a global one.
+    Ready(Permit, Permit),
+    /// A place in the command's line.
+    Queued(Ticket),
+}
+
+impl Reservation {
+    /// Waits for whichever slots haven't been taken yet, the command's
+    /// first.
+    async fn acquire(self, bot: &Rikka) -> (Permit, Permit) {
+        match self {
+            Reservation::Ready(cmd_permit, permit) => (cmd_permit, permit),
+            Reservation::Queued(ticket) => {
+                let cmd_permit = ticket.acquire().await;
+                (cmd_permit, bot.limiter.clone().acquire().await)
+            }
+        }
+    }
+}
+
+/// How long [`Rikka::shutdown`] waits for running handlers.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 pub struct Rikka {
-    pub(crate) cmds: Vec<&'static dyn Command>,
+    cmds: RwLock<Vec<Registered>>,
+    pub(crate) limiter: Arc<Limiter>,
 
     pub(crate) cluster: Cluster,
     pub(crate) http: HttpClient,
     pub(crate) cache: InMemoryCache,
+    pub(crate) storage: Arc<dyn Storage>,
+    pub(crate) webhooks: Webhooks,
+    bus: UnboundedSender<BotEvent>,
+    bus_rx: Mutex<Option<UnboundedReceiver<BotEvent>>>,
+    /// Events from streams added with [`Rikka::forward_events`], merged into
+    /// the main event loop.
+    forwarded: UnboundedSender<(u64, Event)>,
+    forwarded_rx: Mutex<Option<UnboundedReceiver<(u64, Event)>>>,
+
+    pub(crate) suggestions: Arc<Suggestions>,
+    /// Rate limits telling people the bot is down for maintenance.
+    maintenance_notices: Suggestions,
+    pub(crate) audit_retention: Duration,
+    pub(crate) job_poll_interval: std::time::Duration,
+    pub(crate) i18n: Arc<I18n>,
+    pub(crate) presences: Presences,
+    config_loader: Box<dyn Fn() -> Result<Config> + Send + Sync>,
+
+    pub(crate) started: DateTime<Utc>,
+    guilds: RwLock<HashSet<GuildId>>,
+    commands_run: AtomicU64,
+    maintenance: AtomicBool,
+    shutdown: Notify,
 
+    pub(crate) owners: HashSet<UserId>,
     pub(crate) prefix: String,
-    pub(crate) parser: Parser<'static>,
+    pub(crate) parser: RwLock<Parser<'static>>,
 }
 
 impl Rikka {
-    pub async fn new(token: String) -> Result<Self> {
+    pub async fn new(token: String, storage: Arc<dyn Storage>) -> Result<Self> {
+        let http = HttpClient::new(&token);
         let cluster = Cluster::builder(
             &token,
             Intents::all() - Intents::GUILD_PRESENCES - Intents::GUILD_VOICE_STATES,
         )
         .shard_scheme(ShardScheme::Auto)
+        .http_client(http.clone())
         .build()
         .await?;
 
+        Ok(Self::from_parts(cluster, http, storage))
+    }
+
+    /// Creates a bot from an already configured cluster and HTTP client.
+    /// Useful for pointing the bot at something other than Discord, e.g. in
+    /// tests.
+    pub fn from_parts(cluster: Cluster, http: HttpClient, storage: Arc<dyn Storage>) -> Self {
         let cache = InMemoryCache::builder()
             .event_types(EventType::all() - EventType::PRESENCE_UPDATE)
             .build();
 
-        Ok(Rikka {
-            cmds: Vec::default(),
+        let (bus, bus_rx) = mpsc::unbounded();
+        let (forwarded, forwarded_rx) = mpsc::unbounded();
+
+        Rikka {
+            cmds: RwLock::default(),
+            limiter: Arc::new(Limiter::new(DEFAULT_GLOBAL_LIMIT)),
 
             cluster,
-            http: HttpClient::new(&token),
+            http,
             cache,
+            storage,
+            webhooks: Webhooks::new("Rikka"),
+            bus,
+            bus_rx: Mutex::new(Some(bus_rx)),
+            forwarded,
+            forwarded_rx: Mutex::new(Some(forwarded_rx)),
+            suggestions: Arc::new(Suggestions::new(DEFAULT_COOLDOWN)),
+            maintenance_notices: Suggestions::new(DEFAULT_COOLDOWN),
+            audit_retention: Duration::days(DEFAULT_RETENTION_DAYS),
+            job_poll_interval: DEFAULT_POLL_INTERVAL,
+            i18n: Arc::new(I18n::new()),
+            presences: Presences::new(
+                presence::DEFAULT_TEMPLATES
+                    .iter()
+                    .map(|t| (*t).to_owned())
+                    .collect(),
+                presence::DEFAULT_INTERVAL,
+            ),
+            config_loader: Box::new(Config::load),
+
+            started: Utc::now(),
+            guilds: RwLock::default(),
+            commands_run: AtomicU64::new(0),
+            maintenance: AtomicBool::new(false),
+            shutdown: Notify::new(),
+
+            owners: HashSet::default(),
             prefix: "".into(),
-            parser: Parser::new(CommandParserConfig::new()),
-        })
+            parser: RwLock::new(Parser::new(CommandParserConfig::new())),
+        }
+    }
+
+    /// Adds a command. This can be done while the bot is running, and the
+    /// command starts receiving events right away.
+    pub fn register_command<T: Command + 'static>(&self, cmd: T) {
+        self.register_shared_command(Arc::new(cmd));
     }
 
-    pub fn register_command<T: Command + 'static>(&mut self, cmd: T) {
-        let cfg = self.parser.config_mut();
+    /// Like [`Rikka::register_command`], for commands that are already
+    /// shared, e.g. from [`crate::commands::all`].
+    pub fn register_shared_command(&self, cmd: Arc<dyn Command>) {
+        let mut parser = self.parser.write().unwrap();
+        let cfg = parser.config_mut();
 
         for help in cmd.help(None).iter() {
             cfg.add_command(help.name, false);
             for alias in help.aliases {
-                println!("{}", *alias);
                 cfg.add_command(*alias, false);
             }
         }
 
-        self.cmds.push(leak(cmd));
+        self.cmds.write().unwrap().push(Registered {
+            limiter: Arc::new(Limiter::new(cmd.concurrency())),
+            cmd,
+            enabled: true,
+        });
+    }
+
+    /// Removes the command called `name`, returning whether it was
+    /// registered. Handlers that are already running are left to finish.
+    pub fn unregister_command(&self, name: &str) -> bool {
+        let removed = {
+            let mut cmds = self.cmds.write().unwrap();
+            match cmds.iter().position(|r| r.is_named(name)) {
+                Some(idx) => cmds.remove(idx),
+                None => return false,
+            }
+        };
+
+        let mut parser = self.parser.write().unwrap();
+        let cfg = parser.config_mut();
+        for help in removed.cmd.help(None).iter() {
+            cfg.remove_command(help.name);
+            for alias in help.aliases {
+                cfg.remove_command(*alias);
+            }
+        }
+
+        true
+    }
+
+    /// Stops or resumes sending events to the command called `name`.
+    /// Returns the command's name if it's registered.
+    pub fn set_command_enabled(&self, name: &str, enabled: bool) -> Option<&'static str> {
+        let mut cmds = self.cmds.write().unwrap();
+        let reg = cmds.iter_mut().find(|r| r.is_named(name))?;
+        reg.enabled = enabled;
+
+        Some(reg.cmd.name())
+    }
+
+    /// Returns every registered command and whether it's enabled.
+    pub fn command_states(&self) -> Vec<(&'static str, bool)> {
+        self.cmds
+            .read()
+            .unwrap()
+            .iter()
+            .map(|r| (r.cmd.name(), r.enabled))
+            .collect()
+    }
+
+    /// Returns the enabled commands.
+    pub(crate) fn commands(&self) -> Vec<Arc<dyn Command>> {
+        self.enabled_commands()
+            .into_iter()
+            .map(|(cmd, _)| cmd)
+            .collect()
+    }
+
+    fn enabled_commands(&self) -> Vec<(Arc<dyn Command>, Arc<Limiter>)> {
+        self.cmds
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| (r.cmd.clone(), r.limiter.clone()))
+            .collect()
+    }
+
+    /// Sets the maximum number of handler tasks running across all commands.
+    /// Once reached, the event loop stops reading new events until a slot
+    /// frees up. Handlers for a command that's at its own limit wait in its
+    /// line instead of holding a slot.
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.limiter = Arc::new(Limiter::new(limit));
+    }
+
+    /// Returns queue depth and throughput for the global limiter, followed by
+    /// each registered command. The global queue counts every handler that's
+    /// waiting, including those in a command's line.
+    pub fn concurrency_stats(&self) -> Vec<(&'static str, LimiterStats)> {
+        let mut global = self.limiter.stats();
+        let mut stats = Vec::new();
+        for reg in self.cmds.read().unwrap().iter() {
+            let cmd = reg.limiter.stats();
+            global.queued += cmd.queued;
+            stats.push((reg.cmd.name(), cmd));
+        }
+
+        stats.insert(0, ("global", global));
+        stats
+    }
+
+    /// Sets how long to wait between "did you mean" replies in a channel.
+    pub fn set_suggestion_cooldown(&mut self, cooldown: std::time::Duration) {
+        self.suggestions = Arc::new(Suggestions::new(cooldown));
+    }
+
+    /// The guilds the bot is in, as far as it's seen.
+    pub(crate) fn guild_ids(&self) -> Vec<GuildId> {
+        self.guilds.read().unwrap().iter().copied().collect()
+    }
+
+    /// How many channels and distinct users are cached across every guild.
+    pub(crate) fn cached_counts(&self) -> (usize, usize) {
+        let mut channels = 0;
+        let mut users = HashSet::new();
+        for gid in self.guild_ids() {
+            channels += self.cache.guild_channels(gid).map_or(0, |c| c.len());
+            users.extend(self.cache.guild_members(gid).unwrap_or_default());
+        }
+
+        (channels, users.len())
+    }
+
+    /// How many commands have run since the bot started, not counting ones
+    /// that were on cooldown.
+    pub(crate) fn commands_run(&self) -> u64 {
+        self.commands_run.load(Ordering::Relaxed)
+    }
+
+    /// Turns maintenance mode on or off. While it's on, only owners can use
+    /// commands.
+    pub fn set_maintenance(&self, on: bool) {
+        self.maintenance.store(on, Ordering::Relaxed);
+    }
+
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    /// Stops handling events, waits for running handlers to finish and
+    /// returns from [`Rikka::run`].
+    pub fn shutdown(&self) {
+        self.shutdown.notify();
+    }
+
+    /// Reloads the guild settings kept in memory, currently each guild's
+    /// language, from storage. Returns how many guilds were reloaded.
+    pub async fn reload_settings(&self) -> Result<usize> {
+        let guilds = self.guild_ids();
+        for gid in guilds.iter() {
+            self.load_language(*gid).await?;
+        }
+
+        Ok(guilds.len())
+    }
+
+    /// Sets how long command invocations are kept in the audit trail.
+    pub fn set_audit_retention(&mut self, retention: Duration) {
+        self.audit_retention = retention;
+    }
+
+    async fn record_invocation(
+        &self,
+        cmd: &dyn Command,
+        msg: &Message,
+        outcome: Outcome,
+        started: DateTime<Utc>,
+    ) -> Result<()> {
+        let (command, args) = audit::invocation_path(self, cmd, msg);
+        let inv = Invocation {
+            id: msg.id,
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+            user_id: msg.author.id,
+            command,
+            args,
+            outcome,
+            duration_ms: Utc::now()
+                .signed_duration_since(started)
+                .num_milliseconds()
+                .max(0) as u64,
+            at: started,
+        };
+
+        audit::record(&*self.storage, &inv, self.audit_retention).await
+    }
+
+    /// Sends `event` to every enabled command. Delivery happens in the
+    /// background, so this never waits on subscribers.
+    pub fn publish(&self, event: BotEvent) {
+        self.bus
+            .unbounded_send(event)
+            .map_err(|err| println!("publish event: {}", err))
+            .ok();
+    }
+
+    async fn run_bus(self: Arc<Self>, mut events: UnboundedReceiver<BotEvent>) {
+        while let Some(event) = events.next().await {
+            let kind = event.kind();
+            let event = Arc::new(event);
+
+            for (cmd, limiter) in self.enabled_commands() {
+                if !cmd.subscribes().contains(&kind) {
+                    continue;
+                }
+
+                let event = event.clone();
+                let bot = self.clone();
+                let reservation = self.reserve(limiter).await;
+
+                tokio::spawn(async move {
+                    let _permits = reservation.acquire(&bot).await;
+
+                    if let Err(err) = cmd.receive_event(&bot, &event).await {
+                        println!("bot event errored: {}", err)
+                    }
+                });
+            }
+        }
+    }
+
+    /// Sets the presence templates to rotate through. See
+    /// [`presence::render`] for the variables they can use. No templates
+    /// leaves the presence alone.
+    pub fn set_presence_templates(&mut self, templates: Vec<String>) {
+        self.presences.set_templates(templates);
+    }
+
+    /// Sets how long each presence is shown before moving on to the next,
+    /// which is at least [`presence::MIN_INTERVAL`].
+    pub fn set_presence_interval(&mut self, interval: std::time::Duration) {
+        self.presences.set_interval(interval);
+    }
+
+    /// Sets where `bot reload` reads the configuration from, instead of
+    /// [`Config::load`].
+    pub fn set_config_loader(
+        &mut self,
+        loader: impl Fn() -> Result<Config> + Send + Sync + 'static,
+    ) {
+        self.config_loader = Box::new(loader);
+    }
+
+    /// Applies the values set in `config`. Unset ones are left alone.
+    pub fn apply_config(&self, config: &Config) {
+        if let Some(templates) = &config.presences {
+            self.presences.set_templates(templates.clone());
+        }
+        if let Some(interval) = config.presence_interval {
+            self.presences.set_interval(interval);
+        }
+    }
+
+    /// Reads the configuration again and applies it, then reloads the guild
+    /// settings kept in memory. Returns how many guilds were reloaded.
+    pub async fn reload(&self) -> Result<usize> {
+        let config = (self.config_loader)()?;
+        self.apply_config(&config);
+        presence::apply(self).await;
+
+        self.reload_settings().await
+    }
+
+    /// Sets how often to check for due jobs.
+    pub fn set_job_poll_interval(&mut self, interval: std::time::Duration) {
+        self.job_poll_interval = interval;
+    }
+
+    /// Stores `job` to be run once it's due by whichever command handles its
+    /// kind.
+    pub async fn schedule(&self, job: &Job) -> Result<()> {
+        scheduler::schedule(&*self.storage, job).await
+    }
+
+    /// Cancels a scheduled job, returning whether it existed.
+    pub async fn cancel_job(&self, id: JobId) -> Result<bool> {
+        scheduler::cancel(&*self.storage, id).await
+    }
+
+    async fn run_scheduler(self: Arc<Self>) {
+        loop {
+            match scheduler::claim(&*self.storage, Utc::now()).await {
+                Ok(claimed) => {
+                    for claimed in claimed {
+                        let bot = self.clone();
+                        tokio::spawn(async move { bot.run_job(claimed).await });
+                    }
+                }
+                Err(err) => println!("claim jobs: {}", err),
+            }
+
+            tokio::time::delay_for(self.job_poll_interval).await;
+        }
+    }
+
+    async fn run_job(&self, mut claimed: Claimed) {
+        let job = claimed.job.clone();
+        let handler = self
+            .cmds
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.cmd.job_kinds().contains(&job.kind.as_str()))
+            .map(|r| (r.cmd.clone(), r.enabled));
+
+        let ok = match handler {
+            _ if job.kind == audit::SWEEP_JOB => self.sweep_audit().await,
+            // Keep the job around until the command is enabled again.
+            Some((_, false)) => {
+                if let Err(err) = scheduler::release(&*self.storage, &claimed).await {
+                    println!("release job {}: {}", job.id, err);
+                }
+                return;
+            }
+            Some((cmd, true)) => {
+                let run = cmd.run_job(self, &job);
+                tokio::pin!(run);
+
+                // Hold on to the lease for as long as the job runs, so no
+                // other process starts it too.
+                let res = loop {
+                    tokio::select! {
+                        res = &mut run => break res,
+                        _ = tokio::time::delay_for(scheduler::RENEW_INTERVAL) => {
+                            match scheduler::renew(&*self.storage, &mut claimed).await {
+                                Ok(true) => {}
+                                Ok(false) => println!("lost lease on job {}", job.id),
+                                Err(err) => println!("renew job {}: {}", job.id, err),
+                            }
+                        }
+                    }
+                };
+
+                match res {
+                    Ok(()) => true,
+                    Err(err) => {
+                        println!("job {} ({}) errored: {}", job.id, job.kind, err);
+                        false
+                    }
+                }
+            }
+            None => {
+                println!("no handler for job {} ({})", job.id, job.kind);
+                false
+            }
+        };
+
+        if let Err(err) = scheduler::finish(&*self.storage, &claimed, ok).await {
+            println!("finish job {}: {}", job.id, err);
+        }
+    }
+
+    /// Makes sure expired invocations are cleared regularly, even for guilds
+    /// and users that don't run commands anymore.
+    async fn schedule_audit_sweep(self: Arc<Self>) {
+        let every = Duration::hours(audit::SWEEP_INTERVAL_HOURS);
+        let job = Job {
+            // Every process schedules the same job, so it only runs once.
+            id: audit::SWEEP_JOB_ID,
+            ..Job::new(audit::SWEEP_JOB, Utc::now()).every(every)
+        };
+
+        if let Err(err) = self.schedule(&job).await {
+            println!("schedule audit sweep: {}", err);
+        }
+    }
+
+    /// Clears expired invocations, returning whether it worked.
+    async fn sweep_audit(&self) -> bool {
+        match audit::sweep(&*self.storage, Utc::now() - self.audit_retention).await {
+            Ok(_) => true,
+            Err(err) => {
+                println!("sweep audit log: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Returns the names and aliases of every registered command.
+    pub(crate) fn command_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        for cmd in self.commands() {
+            for help in cmd.help(None) {
+                names.push(help.name);
+                names.extend(help.aliases.iter());
+            }
+        }
+
+        names
+    }
+
+    /// Replies with the closest registered command when `msg` uses the
+    /// prefix with an unknown command name.
+    async fn suggest_command(&self, msg: &Message) -> Result<()> {
+        if msg.author.bot
+            || self.prefix.is_empty()
+            || self.parser.read().unwrap().parse(&msg.content).is_some()
+        {
+            return Ok(());
+        }
+
+        let name = match msg
+            .content
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|rest| rest.split_whitespace().next())
+        {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        let names = self.command_names();
+        let suggestion = match closest(name, names.iter().copied()) {
+            Some(suggestion) => suggestion,
+            None => return Ok(()),
+        };
+
+        if let Some(gid) = msg.guild_id {
+            if !settings::get_bool(&*self.storage, gid, settings::SUGGESTIONS, true).await? {
+                return Ok(());
+            }
+        }
+        if !self.suggestions.allow(msg.channel_id) {
+            return Ok(());
+        }
+
+        self.http
+            .create_message(msg.channel_id)
+            .allowed_mentions()
+            .build()
+            .content(tr!(
+                self.locale(msg.guild_id),
+                "unknown-command-suggestion",
+                name = name,
+                suggestion = suggestion
+            ))?
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the locale for replies in `gid`, or the default language for
+    /// DMs.
+    pub(crate) fn locale(&self, gid: Option<GuildId>) -> Locale<'_> {
+        self.i18n.locale(gid)
+    }
+
+    /// Loads the language `gid` has picked, if any.
+    async fn load_language(&self, gid: GuildId) -> Result<()> {
+        let lang = settings::get(&*self.storage, gid, settings::LANGUAGE).await?;
+        let lang = lang.and_then(|raw| String::from_utf8(raw).ok());
+        match lang.and_then(|l| self.i18n.find_language(&l)) {
+            Some(lang) => self.i18n.set_guild_language(gid, lang),
+            None => self.i18n.clear_guild_language(gid),
+        }
+
+        Ok(())
+    }
+
+    pub fn register_owner(&mut self, id: UserId) {
+        self.owners.insert(id);
+    }
+
+    pub fn is_owner(&self, id: UserId) -> bool {
+        self.owners.contains(&id)
     }
 
     pub fn register_prefix(&mut self, pre: impl Into<String>) {
         let pre = pre.into();
         self.prefix = pre.clone();
-        self.parser.config_mut().add_prefix(pre.clone());
+        self.parser
+            .get_mut()
+            .unwrap()
+            .config_mut()
+            .add_prefix(pre.clone());
     }
 
-    pub async fn start(&'static self) -> Result<()> {
+    pub async fn start(self: Arc<Self>) -> Result<()> {
+        let bot = self.clone();
         tokio::spawn(async move {
             println!("booting up shards...");
-            self.cluster.up().await
+            bot.cluster.up().await
         });
 
-        let mut events = self.cluster.events();
+        let events = self.cluster.events();
+        self.clone().run(events).await?;
+
+        self.cluster.down();
+        Ok(())
+    }
+
+    /// Dispatches events from `events` to the registered commands until the
+    /// stream ends or [`Rikka::shutdown`] is called. Scheduled jobs and
+    /// published events are handled in the background.
+    pub async fn run<S>(self: Arc<Self>, events: S) -> Result<()>
+    where
+        S: Stream<Item = (u64, Event)>,
+    {
+        futures::pin_mut!(events);
+        tokio::spawn(self.clone().run_scheduler());
+        tokio::spawn(self.clone().schedule_audit_sweep());
+        tokio::spawn(presence::rotate(self.clone()));
+        if let Some(bus) = self.bus_rx.lock().unwrap().take() {
+            tokio::spawn(self.clone().run_bus(bus));
+        }
+        // Without a receiver, the branch below is never taken.
+        let mut forwarded = self
+            .forwarded_rx
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| mpsc::unbounded().1);
+
+        loop {
+            let (shard, event) = tokio::select! {
+                next = events.next() => match next {
+                    Some(next) => next,
+                    None => break,
+                },
+                Some(next) = forwarded.next() => next,
+                _ = self.shutdown.notified() => {
+                    self.drain().await;
+                    break;
+                }
+            };
 
-        while let Some((shard, event)) = events.next().await {
             self.cache.update(&event);
             let event = Box::new(event);
 
@@ -103,9 +786,32 @@ impl Rikka {
                 dbg!(&event);
             }
 
+            if let Event::Ready(_) = &*event {
+                // Presences don't survive reconnecting.
+                let bot = self.clone();
+                tokio::spawn(async move { presence::apply_to_shard(&bot, shard).await });
+            }
+
+            if let Event::GuildDelete(guild) = &*event {
+                // Unavailable guilds are only down for a while.
+                if !guild.unavailable {
+                    self.guilds.write().unwrap().remove(&guild.id);
+                }
+            }
+
             if let Event::GuildCreate(guild) = *event.clone() {
+                self.guilds.write().unwrap().insert(guild.id);
+
+                let (gid, bot) = (guild.id, self.clone());
+                tokio::spawn(async move {
+                    if let Err(err) = bot.load_language(gid).await {
+                        println!("load guild language: {}", err);
+                    }
+                });
+
+                let bot = self.clone();
                 tokio::spawn(async move {
-                    let shard = self.cluster.shard(shard).unwrap();
+                    let shard = bot.cluster.shard(shard).unwrap();
                     shard
                         .command(&RequestGuildMembersBuilder::new(guild.id).query("", None))
                         .await
@@ -114,47 +820,108 @@ impl Rikka {
                 });
             };
 
-            if let Event::MessageCreate(msg) = *event.clone() {
-                for cmd in self.cmds.iter() {
+            // Commands can be enabled and disabled while events are being
+            // handled, so work from a snapshot.
+            let cmds = self.enabled_commands();
+
+            let msg = match &*event {
+                Event::MessageCreate(msg) if self.blocked_by_maintenance(msg) => {
+                    self.reply_maintenance(msg);
+                    None
+                }
+                Event::MessageCreate(msg) => Some(msg.clone()),
+                _ => None,
+            };
+
+            if let Some(msg) = msg {
+                let unknown = (**msg).clone();
+                let bot = self.clone();
+                let permit = self.limiter.clone().acquire().await;
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    if let Err(err) = bot.suggest_command(&unknown).await {
+                        println!("suggest command: {}", err);
+                    }
+                });
+
+                for (cmd, limiter) in cmds.iter().cloned() {
                     let msg = (**msg).clone();
+                    let bot = self.clone();
+                    let reservation = self.reserve(limiter).await;
 
                     tokio::spawn(async move {
+                        let _permits = reservation.acquire(&bot).await;
+
                         println!("send to cmd: {}", cmd.name());
-                        let res = cmd.receive(self, &msg).await;
+                        let started = Utc::now();
+                        let res = cmd.receive(&bot, &msg).await;
                         println!("end cmd: {}", cmd.name());
-                        match res {
-                            Ok(Some(res)) => {
-                                self.http
-                                    .create_message(msg.channel_id)
-                                    .content(res)
-                                    .unwrap()
-                                    .await
-                                    .map_err(|err| println!("respond to command: {}", err))
-                                    .ok();
+
+                        let outcome = match &res {
+                            Err(CommandError::NoMatch) => None,
+                            Ok(_) => Some(Outcome::Success),
+                            Err(CommandError::BadArgument(_)) | Err(CommandError::Rejected(_)) => {
+                                Some(Outcome::BadArgument)
                             }
+                            Err(CommandError::Denied(_)) => Some(Outcome::Denied),
+                            Err(CommandError::Generic(_)) => Some(Outcome::Error),
+                        };
+
+                        match res {
+                            Ok(Some(res)) => bot.reply(&msg, res).await,
                             Ok(None) => {}
                             Err(CommandError::NoMatch) => {}
+                            Err(CommandError::Denied(reason))
+                            | Err(CommandError::Rejected(reason)) => bot.reply(&msg, reason).await,
+                            Err(CommandError::BadArgument(err)) => {
+                                let loc = bot.locale(msg.guild_id);
+                                let usage = cmd
+                                    .help(Some(&msg))
+                                    .into_iter()
+                                    .next()
+                                    .map(|h| {
+                                        let usage = format!("{}{} {}", bot.prefix, h.name, h.usage);
+                                        format!("\n{}", tr!(loc, "arg-usage", usage = usage))
+                                    })
+                                    .unwrap_or_default();
+
+                                bot.reply(&msg, format!("{}{}", err.localize(&loc), usage))
+                                    .await;
+                            }
                             Err(err) => {
                                 println!("command errored: {:?}", err);
-                                self.http
-                                    .create_message(msg.channel_id)
-                                    .allowed_mentions()
-                                    .build()
-                                    .content(format!("```An error occured: {:?}```", err))
-                                    .unwrap()
-                                    .await
-                                    .ok();
+                                let error = tr!(
+                                    bot.locale(msg.guild_id),
+                                    "error-generic",
+                                    error = format!("{:?}", err)
+                                );
+                                // Leave room for the code block around it.
+                                let error = truncate(&error, MESSAGE_LIMIT - 6);
+                                bot.reply(&msg, format!("```{}```", error)).await;
+                            }
+                        }
+
+                        if let Some(outcome) = outcome {
+                            bot.commands_run.fetch_add(1, Ordering::Relaxed);
+                            if let Err(err) =
+                                bot.record_invocation(&*cmd, &msg, outcome, started).await
+                            {
+                                println!("record invocation: {}", err);
                             }
                         }
                     });
                 }
             };
 
-            for cmd in self.cmds.iter() {
+            for (cmd, limiter) in cmds {
                 let event = event.clone();
+                let bot = self.clone();
+                let reservation = self.reserve(limiter).await;
 
                 tokio::spawn(async move {
-                    if let Err(err) = cmd.receive_raw(self, &event).await {
+                    let _permits = reservation.acquire(&bot).await;
+
+                    if let Err(err) = cmd.receive_raw(&bot, &event).await {
                         println!("raw event errored: {}", err)
                     }
                 });
@@ -164,15 +931,106 @@ impl Rikka {
         Ok(())
     }
 
+    /// Replies to `msg` without pinging anyone, cutting the reply short if
+    /// it's too long for a message.
+    async fn reply(&self, msg: &Message, content: String) {
+        let sent: Result<()> = async {
+            self.http
+                .create_message(msg.channel_id)
+                .allowed_mentions()
+                .build()
+                .content(truncate(&content, MESSAGE_LIMIT))?
+                .await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(err) = sent {
+            println!("respond to command: {}", err);
+        }
+    }
+
+    /// Reserves slots for a handler of the command with `limiter`, before
+    /// it's spawned. If the command has a free slot this waits for a global
+    /// one, which is what stops the event loop when every slot is taken.
+    /// Otherwise the handler gets in the command's line, so a command that's
+    /// at its own limit doesn't hold on to slots every other command needs.
+    async fn reserve(&self, limiter: Arc<Limiter>) -> Reservation {
+        match limiter.try_acquire() {
+            Some(cmd_permit) => {
+                Reservation::Ready(cmd_permit, self.limiter.clone().acquire().await)
+            }
+            None => Reservation::Queued(limiter.enqueue().await),
+        }
+    }
+
+    /// Whether `msg` has to be ignored because of maintenance mode.
+    fn blocked_by_maintenance(&self, msg: &Message) -> bool {
+        self.in_maintenance() && !self.is_owner(msg.author.id)
+    }
+
+    /// Tells the author of a command that it can't be used during
+    /// maintenance. Other messages are ignored silently.
+    fn reply_maintenance(&self, msg: &Message) {
+        if msg.author.bot
+            || self.parser.read().unwrap().parse(&msg.content).is_none()
+            || !self.maintenance_notices.allow(msg.channel_id)
+        {
+            return;
+        }
+
+        let (http, channel) = (self.http.clone(), msg.channel_id);
+        let content = tr!(self.locale(msg.guild_id), "maintenance-active");
+        tokio::spawn(async move {
+            let sent: Result<()> = async {
+                http.create_message(channel).content(content)?.await?;
+                Ok(())
+            }
+            .await;
+
+            if let Err(err) = sent {
+                println!("reply during maintenance: {}", err);
+            }
+        });
+    }
+
+    /// Feeds `events` into the running event loop as coming from `shard`,
+    /// e.g. for a shard that was restarted on its own and so isn't part of
+    /// the cluster's event stream anymore.
+    pub fn forward_events<S>(&self, shard: u64, events: S)
+    where
+        S: Stream<Item = Event> + Send + 'static,
+    {
+        let forwarded = self.forwarded.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(events);
+            while let Some(event) = events.next().await {
+                if forwarded.unbounded_send((shard, event)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Waits for running handlers to finish, up to [`SHUTDOWN_TIMEOUT`].
+    async fn drain(&self) {
+        let start = std::time::Instant::now();
+        loop {
+            let (_, stats) = self.concurrency_stats()[0];
+            if stats.running == 0 && stats.queued == 0 {
+                return;
+            }
+            if start.elapsed() > SHUTDOWN_TIMEOUT {
+                println!("shutting down with {} handlers running", stats.running);
+                return;
+            }
+
+            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
+        }
+    }
+
     // fn generate_help(&self) -> CreateMessage {
     //     let help = super::help::generate_help(&self.cmds);
     //     help
     // }
 }
-
-fn leak<T: Command>(cmd: T) -> &'static T {
-    Box::leak(Box::new(cmd))
-}
-
-unsafe impl Send for Rikka {}
-unsafe impl Sync for Rikka {}
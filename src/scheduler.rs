@@ -0,0 +1,304 @@
+use anyhow::Result;
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Duration, Utc};
+use foundationdb::tuple;
+use futures::FutureExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{transact, Storage, StorageError, Transaction};
+
+pub type JobId = u64;
+
+/// How often each process checks for due jobs.
+pub const DEFAULT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// The most jobs a process claims per poll.
+const CLAIM_BATCH: usize = 32;
+/// How long a claimed job is hidden from other processes. If the process
+/// running it dies, the job becomes due again once this passes.
+const LEASE_SECS: i64 = 60;
+/// How often the lease on a running job is extended.
+pub(crate) const RENEW_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs(LEASE_SECS as u64 / 3);
+/// How many times a failing job is retried before it's dropped. Recurring
+/// jobs skip to their next run instead.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Work to be done at a later time by the command that handles `kind`.
+///
+/// Jobs are stored keyed by when they're due, so they survive restarts.
+/// Claiming a job happens in a transaction, so only one process runs it
+/// even when several share the same storage.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    pub id: JobId,
+    pub kind: String,
+    pub payload: Vec<u8>,
+    pub due: DateTime<Utc>,
+    /// Milliseconds between runs, for recurring jobs.
+    pub every_ms: Option<i64>,
+    pub attempts: u32,
+}
+
+impl Job {
+    pub fn new(kind: impl Into<String>, due: DateTime<Utc>) -> Self {
+        Job {
+            id: rand::random(),
+            kind: kind.into(),
+            payload: Vec::new(),
+            due,
+            every_ms: None,
+            attempts: 0,
+        }
+    }
+
+    pub fn payload<T: Serialize>(mut self, payload: &T) -> Result<Self> {
+        self.payload = serde_cbor::to_vec(payload)?;
+        Ok(self)
+    }
+
+    /// Runs the job again every `every` after its first run.
+    pub fn every(mut self, every: Duration) -> Self {
+        self.every_ms = Some(every.num_milliseconds().max(1));
+        self
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_cbor::from_slice(&self.payload)?)
+    }
+
+    /// When a recurring job should next run, skipping runs that were missed.
+    fn next_due(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let every = Duration::milliseconds(self.every_ms?);
+        let mut next = self.due + every;
+        while next <= now {
+            next = next + every;
+        }
+
+        Some(next)
+    }
+}
+
+/// A job this process claimed and is responsible for finishing.
+pub struct Claimed {
+    pub job: Job,
+    lease_ms: u64,
+}
+
+pub async fn schedule(storage: &dyn Storage, job: &Job) -> Result<()> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, data: &(&Job, Vec<u8>)) -> Result<(), StorageError> {
+        let (job, raw) = data;
+        // Replacing a job moves it, rather than leaving it due twice.
+        if let Some(prev) = t.get(&fmt_index_key(job.id)).await? {
+            t.clear(&fmt_due_key(LittleEndian::read_u64(&prev), job.id));
+        }
+        put(t, job.id, fmt_ms(job.due), raw);
+        Ok(())
+    }
+
+    let raw = serde_cbor::to_vec(job)?;
+    transact(storage, (job, raw), |tx, data| exec(tx, data).boxed()).await?;
+
+    Ok(())
+}
+
+/// Removes a job so it never runs again, returning whether it existed. A run
+/// that's already in progress finishes, but isn't rescheduled.
+pub async fn cancel(storage: &dyn Storage, id: JobId) -> Result<bool> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, id: &JobId) -> Result<bool, StorageError> {
+        match t.get(&fmt_index_key(*id)).await? {
+            Some(raw) => {
+                t.clear(&fmt_due_key(LittleEndian::read_u64(&raw), *id));
+                t.clear(&fmt_index_key(*id));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    Ok(transact(storage, id, |tx, id| exec(tx, id).boxed()).await?)
+}
+
+/// Claims jobs that are due at `now` by pushing them back by the lease.
+pub(crate) async fn claim(storage: &dyn Storage, now: DateTime<Utc>) -> Result<Vec<Claimed>> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, now: &DateTime<Utc>) -> Result<Vec<Claimed>, StorageError> {
+        let sub = due_subspace();
+        let (begin, _) = sub.range();
+        let end = sub.pack(&(fmt_ms(*now) + 1));
+        let lease_ms = fmt_ms(*now + Duration::seconds(LEASE_SECS));
+
+        let mut claimed = Vec::new();
+        for (key, raw) in t.range(&begin, &end, CLAIM_BATCH).await? {
+            let mut job: Job = match serde_cbor::from_slice(&raw) {
+                Ok(job) => job,
+                Err(err) => {
+                    println!("dropping undecodable job: {}", err);
+                    t.clear(&key);
+                    if let Ok((due_ms, id)) = sub.unpack::<(u64, JobId)>(&key) {
+                        if held_until(t, id).await? == Some(due_ms) {
+                            t.clear(&fmt_index_key(id));
+                        }
+                    }
+                    continue;
+                }
+            };
+            job.attempts += 1;
+
+            t.clear(&key);
+            let raw = serde_cbor::to_vec(&job).map_err(anyhow::Error::from)?;
+            put(t, job.id, lease_ms, &raw);
+
+            claimed.push(Claimed { job, lease_ms });
+        }
+
+        Ok(claimed)
+    }
+
+    Ok(transact(storage, now, |tx, now| exec(tx, now).boxed()).await?)
+}
+
+/// Finishes a claimed job. Successful jobs are removed, or rescheduled if
+/// they recur. Failed ones are left to be retried when their lease runs out.
+pub(crate) async fn finish(storage: &dyn Storage, claimed: &Claimed, ok: bool) -> Result<()> {
+    #[inline]
+    async fn exec(
+        t: &dyn Transaction,
+        data: &(&Claimed, bool, DateTime<Utc>),
+    ) -> Result<(), StorageError> {
+        let (claimed, ok, now) = data;
+        let job = &claimed.job;
+
+        // Cancelled, or claimed again after the lease ran out.
+        if held_until(t, job.id).await? != Some(claimed.lease_ms) {
+            return Ok(());
+        }
+
+        if !ok && job.attempts < MAX_ATTEMPTS {
+            return Ok(());
+        }
+
+        t.clear(&fmt_due_key(claimed.lease_ms, job.id));
+        match job.next_due(*now) {
+            Some(due) => {
+                let next = Job {
+                    due,
+                    attempts: 0,
+                    ..job.clone()
+                };
+                let raw = serde_cbor::to_vec(&next).map_err(anyhow::Error::from)?;
+                put(t, next.id, fmt_ms(due), &raw);
+            }
+            None => t.clear(&fmt_index_key(job.id)),
+        }
+
+        Ok(())
+    }
+
+    transact(storage, (claimed, ok, Utc::now()), |tx, data| {
+        exec(tx, data).boxed()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Extends the lease on a job that's still running, so other processes
+/// don't claim it again. Returns whether the job was still ours.
+pub(crate) async fn renew(storage: &dyn Storage, claimed: &mut Claimed) -> Result<bool> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, data: &(&Claimed, u64)) -> Result<bool, StorageError> {
+        let (claimed, lease_ms) = data;
+        let job = &claimed.job;
+        if held_until(t, job.id).await? != Some(claimed.lease_ms) {
+            return Ok(false);
+        }
+
+        t.clear(&fmt_due_key(claimed.lease_ms, job.id));
+        let raw = serde_cbor::to_vec(job).map_err(anyhow::Error::from)?;
+        put(t, job.id, *lease_ms, &raw);
+
+        Ok(true)
+    }
+
+    let lease_ms = fmt_ms(Utc::now() + Duration::seconds(LEASE_SECS));
+    let held = transact(storage, (&*claimed, lease_ms), |tx, data| {
+        exec(tx, data).boxed()
+    })
+    .await?;
+    if held {
+        claimed.lease_ms = lease_ms;
+    }
+
+    Ok(held)
+}
+
+/// Gives a claimed job back without counting the attempt, e.g. because its
+/// command is disabled. It's claimed again once the lease runs out.
+pub(crate) async fn release(storage: &dyn Storage, claimed: &Claimed) -> Result<()> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, claimed: &&Claimed) -> Result<(), StorageError> {
+        let job = &claimed.job;
+        if held_until(t, job.id).await? != Some(claimed.lease_ms) {
+            return Ok(());
+        }
+
+        let job = Job {
+            attempts: job.attempts.saturating_sub(1),
+            ..job.clone()
+        };
+        let raw = serde_cbor::to_vec(&job).map_err(anyhow::Error::from)?;
+        t.set(&fmt_due_key(claimed.lease_ms, job.id), &raw);
+
+        Ok(())
+    }
+
+    transact(storage, claimed, |tx, claimed| exec(tx, claimed).boxed()).await?;
+
+    Ok(())
+}
+
+/// When the job with `id` is due, or its lease runs out.
+async fn held_until(t: &dyn Transaction, id: JobId) -> Result<Option<u64>, StorageError> {
+    Ok(t.get(&fmt_index_key(id))
+        .await?
+        .map(|raw| LittleEndian::read_u64(&raw)))
+}
+
+fn put(t: &dyn Transaction, id: JobId, due_ms: u64, raw: &[u8]) {
+    let mut due_raw = [0u8; 8];
+    LittleEndian::write_u64(&mut due_raw, due_ms);
+
+    t.set(&fmt_due_key(due_ms, id), raw);
+    t.set(&fmt_index_key(id), &due_raw);
+}
+
+fn fmt_ms(at: DateTime<Utc>) -> u64 {
+    at.timestamp_millis().max(0) as u64
+}
+
+const SUBSPACE_PREFIX: &[u8] = b"scheduler";
+
+enum Subspace {
+    Due = 1,
+    Index = 2,
+}
+
+fn due_subspace() -> tuple::Subspace {
+    tuple::Subspace::all()
+        .subspace(&SUBSPACE_PREFIX)
+        .subspace(&(Subspace::Due as u16))
+}
+
+fn fmt_due_key(due_ms: u64, id: JobId) -> Vec<u8> {
+    due_subspace().pack(&(due_ms, id))
+}
+
+fn fmt_index_key(id: JobId) -> Vec<u8> {
+    tuple::Subspace::all()
+        .subspace(&SUBSPACE_PREFIX)
+        .subspace(&(Subspace::Index as u16))
+        .pack(&id)
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use foundationdb::tuple;
+use futures::FutureExt;
+use strum_macros::{EnumString, EnumVariantNames};
+use twilight_model::id::GuildId;
+
+use crate::storage::{transact, Storage, StorageError, Transaction};
+
+/// Whether to reply to unknown commands with a "did you mean" suggestion.
+pub const SUGGESTIONS: &str = "suggestions";
+/// The language tag replies are localized into, e.g. `de`.
+pub const LANGUAGE: &str = "language";
+
+#[derive(EnumString, EnumVariantNames, Clone, Copy, Debug, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Toggle {
+    On,
+    Off,
+}
+
+impl Toggle {
+    pub fn enabled(self) -> bool {
+        self == Toggle::On
+    }
+}
+
+impl From<bool> for Toggle {
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            Toggle::On
+        } else {
+            Toggle::Off
+        }
+    }
+}
+
+pub async fn get(storage: &dyn Storage, gid: GuildId, name: &str) -> Result<Option<Vec<u8>>> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, key: &Vec<u8>) -> Result<Option<Vec<u8>>, StorageError> {
+        t.get(key).await
+    }
+
+    let key = fmt_setting_key(gid, name);
+    Ok(transact(storage, key, |tx, key| exec(tx, key).boxed()).await?)
+}
+
+pub async fn set(storage: &dyn Storage, gid: GuildId, name: &str, val: &[u8]) -> Result<()> {
+    #[inline]
+    async fn exec(t: &dyn Transaction, kv: &(Vec<u8>, &[u8])) -> Result<(), StorageError> {
+        t.set(&kv.0, kv.1);
+        Ok(())
+    }
+
+    let key = fmt_setting_key(gid, name);
+    transact(storage, (key, val), |tx, kv| exec(tx, kv).boxed()).await?;
+
+    Ok(())
+}
+
+pub async fn get_bool(
+    storage: &dyn Storage,
+    gid: GuildId,
+    name: &str,
+    default: bool,
+) -> Result<bool> {
+    Ok(get(storage, gid, name)
+        .await?
+        .and_then(|raw| raw.first().copied())
+        .map(|b| b != 0)
+        .unwrap_or(default))
+}
+
+pub async fn set_bool(storage: &dyn Storage, gid: GuildId, name: &str, val: bool) -> Result<()> {
+    set(storage, gid, name, &[val as u8]).await
+}
+
+const SUBSPACE_PREFIX: &[u8] = b"settings";
+
+fn fmt_setting_key(gid: GuildId, name: &str) -> Vec<u8> {
+    tuple::Subspace::all()
+        .subspace(&SUBSPACE_PREFIX)
+        .subspace(&gid.0)
+        .pack(&name)
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use foundationdb::options::MutationType;
+use foundationdb::{Database, FdbError, RangeOption};
+use futures::TryStreamExt;
+
+use super::{KeyValue, Storage, StorageError, Transaction};
+
+pub struct FdbStorage {
+    db: Database,
+}
+
+impl FdbStorage {
+    /// Opens the default cluster file. The FDB network thread must already be
+    /// running.
+    pub fn new() -> Result<FdbStorage> {
+        Ok(FdbStorage {
+            db: Database::default()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for FdbStorage {
+    async fn begin(&self) -> Result<Box<dyn Transaction>, StorageError> {
+        Ok(Box::new(FdbTransaction(self.db.create_trx()?)))
+    }
+}
+
+struct FdbTransaction(foundationdb::Transaction);
+
+#[async_trait]
+impl Transaction for FdbTransaction {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.0.get(key, false).await?.map(|val| val.to_vec()))
+    }
+
+    async fn range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, StorageError> {
+        let opt = RangeOption {
+            limit: Some(limit),
+            ..RangeOption::from((begin.to_vec(), end.to_vec()))
+        };
+
+        let mut kvs = Vec::new();
+        let mut batches = self.0.get_ranges(opt, false);
+        while let Some(batch) = batches.try_next().await? {
+            for kv in batch.iter() {
+                kvs.push((kv.key().to_vec(), kv.value().to_vec()));
+            }
+        }
+
+        Ok(kvs)
+    }
+
+    fn set(&self, key: &[u8], value: &[u8]) {
+        self.0.set(key, value)
+    }
+
+    fn clear(&self, key: &[u8]) {
+        self.0.clear(key)
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        self.0.clear_range(begin, end)
+    }
+
+    fn atomic_add(&self, key: &[u8], delta: i64) {
+        self.0
+            .atomic_op(key, &delta.to_le_bytes(), MutationType::Add)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), StorageError> {
+        self.0.commit().await.map_err(FdbError::from)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::{KeyValue, Storage, StorageError, Transaction};
+
+/// A [`Storage`] that lives entirely in memory, for tests and local
+/// development without a FoundationDB cluster.
+///
+/// Conflict detection is coarse: a transaction that read anything and wrote
+/// something fails to commit if any other transaction committed after it
+/// began. Read-only transactions always commit, like in FoundationDB.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    data: BTreeMap<Vec<u8>, Vec<u8>>,
+    version: u64,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn begin(&self) -> Result<Box<dyn Transaction>, StorageError> {
+        let read_version = self.inner.lock().unwrap().version;
+
+        Ok(Box::new(MemoryTransaction {
+            store: self.inner.clone(),
+            read_version,
+            did_read: AtomicBool::new(false),
+            writes: Mutex::default(),
+        }))
+    }
+}
+
+enum Write {
+    Set(Vec<u8>, Vec<u8>),
+    Clear(Vec<u8>),
+    ClearRange(Vec<u8>, Vec<u8>),
+    Add(Vec<u8>, i64),
+}
+
+impl Write {
+    fn apply(&self, data: &mut BTreeMap<Vec<u8>, Vec<u8>>) {
+        match self {
+            Write::Set(key, val) => {
+                data.insert(key.clone(), val.clone());
+            }
+            Write::Clear(key) => {
+                data.remove(key);
+            }
+            Write::ClearRange(begin, end) => {
+                let keys: Vec<_> = data
+                    .range(begin.clone()..end.clone())
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                for key in keys {
+                    data.remove(&key);
+                }
+            }
+            Write::Add(key, delta) => {
+                let mut raw = [0u8; 8];
+                if let Some(cur) = data.get(key) {
+                    let len = cur.len().min(8);
+                    raw[..len].copy_from_slice(&cur[..len]);
+                }
+
+                let sum = i64::from_le_bytes(raw.try_into().unwrap()).wrapping_add(*delta);
+                data.insert(key.clone(), sum.to_le_bytes().to_vec());
+            }
+        }
+    }
+}
+
+struct MemoryTransaction {
+    store: Arc<Mutex<Inner>>,
+    read_version: u64,
+    did_read: AtomicBool,
+    writes: Mutex<Vec<Write>>,
+}
+
+impl MemoryTransaction {
+    /// Returns the committed pairs in `[begin, end)` with this transaction's
+    /// writes applied on top.
+    fn view(&self, begin: &[u8], end: &[u8]) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        self.did_read.store(true, Ordering::Relaxed);
+
+        let mut view: BTreeMap<_, _> = self
+            .store
+            .lock()
+            .unwrap()
+            .data
+            .range(begin.to_vec()..end.to_vec())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        for write in self.writes.lock().unwrap().iter() {
+            write.apply(&mut view);
+        }
+
+        view.range(begin.to_vec()..end.to_vec())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    fn push(&self, write: Write) {
+        self.writes.lock().unwrap().push(write);
+    }
+}
+
+#[async_trait]
+impl Transaction for MemoryTransaction {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        let mut end = key.to_vec();
+        end.push(0);
+
+        Ok(self.view(key, &end).remove(key))
+    }
+
+    async fn range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, StorageError> {
+        if begin >= end {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.view(begin, end).into_iter().take(limit).collect())
+    }
+
+    fn set(&self, key: &[u8], value: &[u8]) {
+        self.push(Write::Set(key.to_vec(), value.to_vec()))
+    }
+
+    fn clear(&self, key: &[u8]) {
+        self.push(Write::Clear(key.to_vec()))
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        if begin < end {
+            self.push(Write::ClearRange(begin.to_vec(), end.to_vec()))
+        }
+    }
+
+    fn atomic_add(&self, key: &[u8], delta: i64) {
+        self.push(Write::Add(key.to_vec(), delta))
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), StorageError> {
+        let writes = std::mem::take(&mut *self.writes.lock().unwrap());
+        let mut store = self.store.lock().unwrap();
+
+        if writes.is_empty() {
+            return Ok(());
+        }
+        if self.did_read.load(Ordering::Relaxed) && store.version != self.read_version {
+            return Err(StorageError::Conflict);
+        }
+
+        for write in writes.iter() {
+            write.apply(&mut store.data);
+        }
+        store.version += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_conflicting_writes_fail() {
+        let storage = MemoryStorage::new();
+        let reader = storage.begin().await.unwrap();
+        let writer = storage.begin().await.unwrap();
+        reader.get(b"a").await.unwrap();
+        writer.get(b"a").await.unwrap();
+
+        let other = storage.begin().await.unwrap();
+        other.set(b"a", b"1");
+        other.commit().await.unwrap();
+
+        reader.commit().await.unwrap();
+        writer.set(b"a", b"2");
+        assert!(matches!(writer.commit().await, Err(StorageError::Conflict)));
+    }
+}
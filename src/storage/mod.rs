@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use thiserror::Error as EnumError;
+
+mod fdb;
+mod memory;
+
+pub use fdb::FdbStorage;
+pub use memory::MemoryStorage;
+
+/// How many times [`transact`] retries a transaction that failed with a
+/// retryable error before giving up.
+const MAX_RETRIES: usize = 10;
+
+pub type KeyValue = (Vec<u8>, Vec<u8>);
+
+#[derive(EnumError, Debug)]
+pub enum StorageError {
+    #[error("transaction conflicted with a concurrent transaction")]
+    Conflict,
+    #[error(transparent)]
+    Fdb(#[from] foundationdb::FdbError),
+    #[error(transparent)]
+    Generic(#[from] anyhow::Error),
+}
+
+impl StorageError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            StorageError::Conflict => true,
+            StorageError::Fdb(err) => err.is_retryable(),
+            StorageError::Generic(_) => false,
+        }
+    }
+}
+
+/// An ordered key-value store with serializable transactions.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn begin(&self) -> Result<Box<dyn Transaction>, StorageError>;
+}
+
+/// A single transaction against a [`Storage`]. Reads observe the
+/// transaction's own writes, and nothing is visible to other transactions
+/// until [`Transaction::commit`] succeeds.
+#[async_trait]
+pub trait Transaction: Send + Sync {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Returns up to `limit` pairs with keys in `[begin, end)`, in key order.
+    async fn range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, StorageError>;
+
+    fn set(&self, key: &[u8], value: &[u8]);
+
+    fn clear(&self, key: &[u8]);
+
+    /// Clears every key in `[begin, end)`.
+    fn clear_range(&self, begin: &[u8], end: &[u8]);
+
+    /// Adds `delta` to the little endian integer stored at `key` without
+    /// reading it, so concurrent adds never conflict. Missing keys count as
+    /// zero.
+    fn atomic_add(&self, key: &[u8], delta: i64);
+
+    async fn commit(self: Box<Self>) -> Result<(), StorageError>;
+}
+
+/// Runs `f` in a transaction and commits it, retrying the whole transaction
+/// when it fails with a retryable error.
+pub async fn transact<D, T, F>(db: &dyn Storage, data: D, f: F) -> Result<T, StorageError>
+where
+    D: Send + Sync,
+    T: Send,
+    F: for<'t> Fn(&'t dyn Transaction, &'t D) -> BoxFuture<'t, Result<T, StorageError>>
+        + Send
+        + Sync,
+{
+    let mut retries = 0;
+
+    loop {
+        let tx = db.begin().await?;
+        let res = match f(&*tx, &data).await {
+            Ok(val) => tx.commit().await.map(|_| val),
+            Err(err) => Err(err),
+        };
+
+        match res {
+            Err(err) if err.is_retryable() && retries < MAX_RETRIES => retries += 1,
+            res => return res,
+        }
+    }
+}
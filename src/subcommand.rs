@@ -0,0 +1,249 @@
+use futures::future::BoxFuture;
+use twilight_model::channel::Message;
+
+use crate::error::{CommandError, CommandResult};
+use crate::help::help_description;
+use crate::i18n::Locale;
+use crate::parse::Args;
+use crate::permissions::{require_all, Requirement};
+use crate::rikka::Rikka;
+use crate::suggest::closest;
+
+/// Handles a subcommand. `C` is the command that owns the tree, and the
+/// arguments start after the subcommand's name.
+pub type Handler<C> =
+    for<'a> fn(&'a C, &'a Rikka, &'a Message, Args<'a>) -> BoxFuture<'a, CommandResult>;
+
+/// A node in a tree of nested subcommands, e.g. `logs message enable`.
+///
+/// Walking the tree consumes arguments for as long as they name a child.
+/// The deepest node reached runs its handler with the remaining arguments,
+/// or replies with the options it expected if it has no handler.
+pub struct Subcommand<C> {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    usage: String,
+    examples: &'static [&'static str],
+    requires: Vec<Requirement>,
+
+    handler: Option<Handler<C>>,
+    children: Vec<Subcommand<C>>,
+}
+
+/// A type erased view of a [`Subcommand`] for help pages.
+pub trait SubcommandInfo: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn aliases(&self) -> &'static [&'static str];
+    fn usage(&self) -> &str;
+    fn examples(&self) -> &'static [&'static str];
+    fn requires(&self) -> &[Requirement];
+    fn children(&self) -> Vec<&dyn SubcommandInfo>;
+
+    /// Returns the direct child called `name`, matching aliases too.
+    fn child(&self, name: &str) -> Option<&dyn SubcommandInfo> {
+        self.children()
+            .into_iter()
+            .find(|c| c.name() == name || c.aliases().contains(&name))
+    }
+}
+
+impl<C: Sync> Subcommand<C> {
+    pub fn new(name: &'static str) -> Self {
+        Subcommand {
+            name,
+            aliases: &[],
+            usage: String::new(),
+            examples: &[],
+            requires: Vec::new(),
+
+            handler: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Describes the arguments the handler takes, e.g. `[channel]`. See
+    /// [`crate::extract::usage`] to generate it from the argument types.
+    pub fn usage(mut self, usage: impl Into<String>) -> Self {
+        self.usage = usage.into();
+        self
+    }
+
+    /// Example invocations without the prefix, e.g. `log message enable
+    /// #logs`.
+    pub fn examples(mut self, examples: &'static [&'static str]) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Adds a requirement for this node. Requirements apply to every node
+    /// below it as well.
+    pub fn requires(mut self, req: Requirement) -> Self {
+        self.requires.push(req);
+        self
+    }
+
+    pub fn handler(mut self, handler: Handler<C>) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    pub fn child(mut self, child: Subcommand<C>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    fn find(&self, name: &str) -> Option<&Subcommand<C>> {
+        self.children
+            .iter()
+            .find(|c| c.name == name || c.aliases.contains(&name))
+    }
+
+    /// Walks the tree using `args` and runs the handler that was found.
+    /// `args` should start after the command's own name.
+    pub async fn dispatch<'a>(
+        &'a self,
+        cmd: &'a C,
+        bot: &'a Rikka,
+        msg: &'a Message,
+        mut args: Args<'a>,
+    ) -> CommandResult {
+        let loc = bot.locale(msg.guild_id);
+        let mut node = self;
+        let mut path = vec![self.name];
+
+        loop {
+            require_all(bot, msg, &node.requires)?;
+
+            let mut peek = args.clone();
+            let next = peek.next();
+
+            if let Some(child) = next.as_deref().and_then(|arg| node.find(arg)) {
+                node = child;
+                path.push(child.name);
+                args = peek;
+                continue;
+            }
+
+            return match (next.as_deref(), node.handler) {
+                (_, Some(handler)) => match handler(cmd, bot, msg, args).await {
+                    Err(CommandError::BadArgument(err)) => Err(CommandError::Rejected(format!(
+                        "{}\n{}",
+                        err.localize(&loc),
+                        tr!(
+                            loc,
+                            "arg-usage",
+                            usage = format!("{}{} {}", bot.prefix, path.join(" "), node.usage)
+                        )
+                    ))),
+                    res => res,
+                },
+                (Some("help"), None) | (None, None) => Ok(Some(fmt_node_help(
+                    &loc,
+                    &bot.prefix,
+                    &path.join(" "),
+                    node,
+                ))),
+                (Some(arg), None) => {
+                    Err(CommandError::Rejected(fmt_unknown_option(&loc, node, arg)))
+                }
+            };
+        }
+    }
+}
+
+impl<C: Sync> SubcommandInfo for Subcommand<C> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        self.aliases
+    }
+
+    fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        self.examples
+    }
+
+    fn requires(&self) -> &[Requirement] {
+        &self.requires
+    }
+
+    fn children(&self) -> Vec<&dyn SubcommandInfo> {
+        self.children
+            .iter()
+            .map(|c| c as &dyn SubcommandInfo)
+            .collect()
+    }
+}
+
+/// Formats the names of `node`'s children as e.g. `` `enable`, `disable` ``.
+pub fn fmt_options(node: &dyn SubcommandInfo) -> String {
+    node.children()
+        .iter()
+        .map(|c| format!("`{}`", c.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Tells the user `arg` isn't one of `node`'s children, suggesting the
+/// closest one if it looks like a typo.
+pub fn fmt_unknown_option(loc: &Locale, node: &dyn SubcommandInfo, arg: &str) -> String {
+    let children = node.children();
+    let names = children
+        .iter()
+        .flat_map(|c| std::iter::once(c.name()).chain(c.aliases().iter().copied()));
+
+    match closest(arg, names) {
+        Some(name) => tr!(
+            loc,
+            "unknown-option-suggestion",
+            option = arg,
+            suggestion = name
+        ),
+        None => tr!(
+            loc,
+            "unknown-option",
+            option = arg,
+            options = fmt_options(node)
+        ),
+    }
+}
+
+/// A plain text summary of `node` and its children. `path` is the full
+/// command path leading to `node`, e.g. `logs message`.
+pub fn fmt_node_help(loc: &Locale, prefix: &str, path: &str, node: &dyn SubcommandInfo) -> String {
+    let mut out = format!("**{}{}**", prefix, path);
+    if !node.usage().is_empty() {
+        out.push_str(&format!(" `{}`", node.usage()));
+    }
+    let description = help_description(loc, path);
+    if !description.is_empty() {
+        out.push_str(&format!("\n{}", description));
+    }
+
+    let children = node.children();
+    if !children.is_empty() {
+        out.push_str(&format!("\n\n{}", tr!(loc, "subcommands")));
+        for child in children {
+            out.push_str(&format!("\n• `{}`", child.name()));
+            if !child.usage().is_empty() {
+                out.push_str(&format!(" `{}`", child.usage()));
+            }
+            let description = help_description(loc, &format!("{} {}", path, child.name()));
+            if !description.is_empty() {
+                out.push_str(&format!(" - {}", description));
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use twilight_model::id::ChannelId;
+
+/// How long to wait before suggesting another command in the same channel.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Rate limits "did you mean" replies per channel, so a conversation that
+/// happens to start with the prefix doesn't get spammed.
+pub struct Suggestions {
+    cooldown: Duration,
+    last: Mutex<HashMap<ChannelId, Instant>>,
+}
+
+impl Suggestions {
+    pub fn new(cooldown: Duration) -> Self {
+        Suggestions {
+            cooldown,
+            last: Mutex::default(),
+        }
+    }
+
+    /// Returns whether a suggestion may be sent to `cid` right now, and if so
+    /// starts its cooldown.
+    pub fn allow(&self, cid: ChannelId) -> bool {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap();
+        last.retain(|_, at| now.duration_since(*at) < self.cooldown);
+
+        if last.contains_key(&cid) {
+            return false;
+        }
+        last.insert(cid, now);
+        true
+    }
+}
+
+/// Returns the candidate closest to `input`, if any is close enough to be a
+/// plausible typo.
+pub fn closest<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let input = input.to_lowercase();
+    // Allow roughly one edit per three characters, but always at least one.
+    let max = (input.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|c| (levenshtein(&input, &c.to_lowercase()), c))
+        .filter(|(dist, _)| *dist > 0 && *dist <= max)
+        .min_by_key(|(dist, _)| *dist)
+        .map(|(_, c)| c)
+}
+
+/// The number of single character insertions, deletions, substitutions or
+/// swaps of two neighbouring characters needed to turn `a` into `b`. Swaps
+/// count as one edit since they're the most common typo.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // The rows for the previous two characters of `a` and the current one.
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for i in 0..a.len() {
+        cur[0] = i + 1;
+        for j in 0..b.len() {
+            let cost = if a[i] == b[j] { 0 } else { 1 };
+            cur[j + 1] = (prev[j + 1] + 1).min(cur[j] + 1).min(prev[j] + cost);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                cur[j + 1] = cur[j + 1].min(prev2[j - 1] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("ping", "ping"), 0);
+        assert_eq!(levenshtein("", "ping"), 4);
+        assert_eq!(levenshtein("pin", "ping"), 1);
+        assert_eq!(levenshtein("pong", "ping"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn levenshtein_counts_swaps_once() {
+        assert_eq!(levenshtein("pign", "ping"), 1);
+        assert_eq!(levenshtein("hlep", "help"), 1);
+        assert_eq!(levenshtein("ab", "ba"), 1);
+        assert_eq!(levenshtein("ca", "abc"), 3);
+    }
+
+    #[test]
+    fn closest_picks_the_nearest_candidate() {
+        let cmds = ["ping", "help", "played", "purge"];
+        assert_eq!(closest("pign", cmds.iter().copied()), Some("ping"));
+        assert_eq!(closest("HLEP", cmds.iter().copied()), Some("help"));
+        assert_eq!(closest("playde", cmds.iter().copied()), Some("played"));
+    }
+
+    #[test]
+    fn closest_ignores_exact_and_distant_matches() {
+        let cmds = ["ping", "help"];
+        assert_eq!(closest("ping", cmds.iter().copied()), None);
+        assert_eq!(closest("pizza", cmds.iter().copied()), None);
+        assert_eq!(closest("xy", cmds.iter().copied()), None);
+    }
+
+    #[test]
+    fn suggestions_cool_down_per_channel() {
+        let s = Suggestions::new(Duration::from_secs(60));
+        assert!(s.allow(ChannelId(1)));
+        assert!(!s.allow(ChannelId(1)));
+        assert!(s.allow(ChannelId(2)));
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use twilight_http::{Client as HttpClient, Error as HttpError};
+use twilight_model::channel::embed::Embed;
+use twilight_model::id::{ChannelId, WebhookId};
+
+use crate::limits::KeyedLocks;
+
+/// The most embeds Discord accepts in a single webhook message.
+pub const MAX_EMBEDS: usize = 10;
+
+/// Posts to channels through webhooks owned by the bot, which have their own
+/// rate limits and can use a custom name and avatar per message.
+///
+/// A webhook is created the first time a channel is posted to, or reused if
+/// the channel already has one with the same name. If it's deleted later,
+/// a new one is created on the next post. Channels the bot can't manage
+/// webhooks in get regular messages instead.
+pub struct Webhooks {
+    name: String,
+    hooks: RwLock<HashMap<ChannelId, Hook>>,
+    /// Held while looking up or creating a channel's webhook, so concurrent
+    /// first posts don't each create one.
+    pending: KeyedLocks<ChannelId>,
+}
+
+#[derive(Clone)]
+struct Hook {
+    id: WebhookId,
+    token: String,
+}
+
+/// A message to post through a webhook.
+#[derive(Default)]
+pub struct WebhookMessage {
+    content: Option<String>,
+    username: Option<String>,
+    avatar_url: Option<String>,
+    embeds: Vec<Embed>,
+}
+
+impl WebhookMessage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Overrides the webhook's name for this message.
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Overrides the webhook's avatar for this message.
+    pub fn avatar_url(mut self, avatar_url: impl Into<String>) -> Self {
+        self.avatar_url = Some(avatar_url.into());
+        self
+    }
+
+    /// Adds an embed. Messages with more than [`MAX_EMBEDS`] are split over
+    /// several requests.
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.embeds.push(embed);
+        self
+    }
+}
+
+impl Webhooks {
+    /// `name` is used for the webhooks the bot creates, and to recognize
+    /// them again after a restart.
+    pub fn new(name: impl Into<String>) -> Self {
+        Webhooks {
+            name: name.into(),
+            hooks: RwLock::default(),
+            pending: KeyedLocks::default(),
+        }
+    }
+
+    pub async fn send(&self, http: &HttpClient, cid: ChannelId, msg: WebhookMessage) -> Result<()> {
+        let mut batches: Vec<&[Embed]> = msg.embeds.chunks(MAX_EMBEDS).collect();
+        if batches.is_empty() {
+            batches.push(&[]);
+        }
+
+        let mut hook = match self.hook(http, cid).await {
+            Ok(hook) => hook,
+            Err(err) => {
+                println!(
+                    "no webhook in {}, sending a message instead: {:#}",
+                    cid, err
+                );
+                return self
+                    .send_message(http, cid, msg.content.as_ref(), &msg.embeds)
+                    .await;
+            }
+        };
+
+        for (i, embeds) in batches.into_iter().enumerate() {
+            // Only the first request carries the content, so it isn't
+            // repeated above every batch of embeds.
+            let content = if i == 0 { msg.content.as_ref() } else { None };
+
+            match self.execute(http, &hook, &msg, content, embeds).await {
+                Err(HttpError::Response { status, .. }) if status == StatusCode::NOT_FOUND => {
+                    // Someone deleted the webhook, make a new one and retry.
+                    self.forget(cid, &hook);
+                    hook = match self.hook(http, cid).await {
+                        Ok(hook) => hook,
+                        Err(err) => {
+                            println!(
+                                "couldn't recreate webhook in {}, sending a message instead: {:#}",
+                                cid, err
+                            );
+                            let rest = &msg.embeds[i * MAX_EMBEDS..];
+                            return self.send_message(http, cid, content, rest).await;
+                        }
+                    };
+                    self.execute(http, &hook, &msg, content, embeds)
+                        .await
+                        .context("execute recreated webhook")?;
+                }
+                res => res.context("execute webhook")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        http: &HttpClient,
+        hook: &Hook,
+        msg: &WebhookMessage,
+        content: Option<&String>,
+        embeds: &[Embed],
+    ) -> Result<(), HttpError> {
+        let mut req = http
+            .execute_webhook(hook.id, hook.token.clone())
+            .embeds(embeds.to_vec());
+        if let Some(content) = content {
+            req = req.content(content.clone());
+        }
+        if let Some(username) = &msg.username {
+            req = req.username(username.clone());
+        }
+        if let Some(avatar_url) = &msg.avatar_url {
+            req = req.avatar_url(avatar_url.clone());
+        }
+
+        req.await.map(|_| ())
+    }
+
+    /// Posts `content` and `embeds` as the bot itself, one embed per
+    /// message. The name and avatar overrides are lost.
+    async fn send_message(
+        &self,
+        http: &HttpClient,
+        cid: ChannelId,
+        content: Option<&String>,
+        embeds: &[Embed],
+    ) -> Result<()> {
+        let mut embeds = embeds.iter();
+        let first = embeds.next();
+
+        if content.is_some() || first.is_some() {
+            let mut req = http.create_message(cid).allowed_mentions().build();
+            if let Some(content) = content {
+                req = req.content(content.clone()).context("add content")?;
+            }
+            if let Some(embed) = first {
+                req = req.embed(embed.clone()).context("add embed")?;
+            }
+            req.await.context("send message")?;
+        }
+
+        for embed in embeds {
+            http.create_message(cid)
+                .allowed_mentions()
+                .build()
+                .embed(embed.clone())
+                .context("add embed")?
+                .await
+                .context("send message")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the webhook for `cid`, reusing or creating one if it isn't
+    /// cached yet.
+    async fn hook(&self, http: &HttpClient, cid: ChannelId) -> Result<Hook> {
+        if let Some(hook) = self.cached(cid) {
+            return Ok(hook);
+        }
+
+        let _guard = self.pending.lock(cid).await;
+
+        // Someone else may have found or created it while we waited.
+        if let Some(hook) = self.cached(cid) {
+            return Ok(hook);
+        }
+
+        let existing = http
+            .channel_webhooks(cid)
+            .await
+            .context("list channel webhooks")?
+            .into_iter()
+            .find(|w| w.name.as_deref() == Some(self.name.as_str()) && w.token.is_some());
+
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => http
+                .create_webhook(cid, self.name.clone())
+                .await
+                .context("create webhook")?,
+        };
+
+        let hook = Hook {
+            id: webhook.id,
+            token: webhook.token.context("webhook has no token")?,
+        };
+        self.hooks.write().unwrap().insert(cid, hook.clone());
+
+        Ok(hook)
+    }
+
+    fn cached(&self, cid: ChannelId) -> Option<Hook> {
+        self.hooks.read().unwrap().get(&cid).cloned()
+    }
+
+    /// Drops `hook` from the cache, unless it was already replaced.
+    fn forget(&self, cid: ChannelId, hook: &Hook) {
+        let mut hooks = self.hooks.write().unwrap();
+        if hooks.get(&cid).map(|h| h.id) == Some(hook.id) {
+            hooks.remove(&cid);
+        }
+    }
+}
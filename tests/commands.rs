@@ -0,0 +1,1382 @@
+mod common;
+
+use async_trait::async_trait;
+use common::*;
+use hyper::Method;
+use rikka_rs::commands::{
+    audit::Audit,
+    general::Ping,
+    help::Help,
+    info::{Avatar, ChannelInfo, RoleInfo, ServerInfo, UserInfo},
+    logs::Logs,
+    owner::{Commands, Control},
+    played::{Entries, GameEntry, Played, StoredEntries},
+    say::Say,
+    settings::Settings,
+    stats::Stats,
+};
+use rikka_rs::storage::MemoryStorage;
+use rikka_rs::{
+    BotEvent, BotEventKind, Command, CommandError, CommandHelp, CommandResult, Config,
+    LatencyProbe, ModerationAction, ModerationKind, Requirement, Rikka,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use twilight_gateway::Event;
+use twilight_model::channel::Message;
+use twilight_model::gateway::payload::MessageCreate;
+use twilight_model::id::{GuildId, UserId};
+
+#[tokio::test]
+async fn ping_reports_latencies() {
+    let h = Harness::new(|r| r.register_command(Ping {})).await;
+    h.say(42, "rt.ping");
+
+    let reqs = h.requests(2).await;
+    assert!(reqs[0].is_message_to(CHANNEL_ID));
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+
+    assert_eq!(reqs[1].method, Method::PATCH);
+    assert!(reqs[1]
+        .path
+        .starts_with(&format!("/channels/{}/messages/", CHANNEL_ID)));
+    let embed = reqs[1].embeds()[0];
+    assert_eq!(embed["title"], "Pong!");
+    assert!(field(embed, "REST").ends_with("ms"));
+    assert!(field(embed, "Storage").ends_with("ms"));
+}
+
+#[tokio::test]
+async fn ping_gives_up_on_slow_dependencies() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Block);
+    })
+    .await;
+    h.say(42, "rt.ping");
+
+    let reqs = h.requests(2).await;
+    let embed = reqs[1].embeds()[0];
+    assert!(field(embed, "Storage").ends_with("ms"));
+    assert_eq!(field(embed, "Object storage"), "timed out");
+}
+
+#[tokio::test]
+async fn ignores_other_prefixes_and_bots() {
+    let h = Harness::new(|r| r.register_command(Ping {})).await;
+    h.say(42, "r.ping");
+    h.say(42, "ping");
+    h.message_create(message(7, BOT_ID, "rt.ping"));
+
+    assert!(h.settle().await.is_empty());
+}
+
+#[tokio::test]
+async fn help_lists_commands_by_section() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+    })
+    .await;
+    h.say(42, "rt.help");
+
+    let reqs = h.requests(1).await;
+    assert!(reqs[0].is_message_to(CHANNEL_ID));
+
+    let embeds = reqs[0].embeds();
+    assert_eq!(embeds.len(), 1);
+
+    let general = embeds[0]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == "General")
+        .expect("general section");
+    let value = general["value"].as_str().unwrap();
+    assert!(value.contains("`ping`"));
+    assert!(value.contains("`help`"));
+}
+
+/// A harness with `Played` reading from memory, seeded with `games` for
+/// user 42.
+async fn played_harness(games: &[(&str, i64)]) -> Harness {
+    let storage = MemoryStorage::new();
+    let entries = StoredEntries::new(Arc::new(storage.clone()));
+    if !games.is_empty() {
+        let now = chrono::Utc::now();
+        let seeded = Entries {
+            first_seen: now - chrono::Duration::days(3),
+            last_updated: now,
+            games: games
+                .iter()
+                .map(|(name, secs)| GameEntry {
+                    name: name.to_string(),
+                    secs: *secs,
+                    last_played: None,
+                })
+                .collect(),
+        };
+        entries.write(UserId(42), &seeded).await.unwrap();
+    }
+
+    let played = Played::with_source(entries);
+    let h = Harness::with_storage(storage, move |r| r.register_command(played)).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+    h
+}
+
+#[tokio::test]
+async fn played_without_entries() {
+    let h = played_harness(&[]).await;
+    h.say(42, "rt.played");
+
+    let reqs = h.requests(1).await;
+    assert!(reqs[0].is_message_to(CHANNEL_ID));
+    assert_eq!(reqs[0].content(), Some("No entries found for user42#0001"));
+}
+
+#[tokio::test]
+async fn played_shows_games_and_totals() {
+    let h = played_harness(&[("Celeste", 90), ("Hades", 3 * 3600 + 60)]).await;
+    h.say(42, "rt.played");
+
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "user42");
+    assert_eq!(
+        field(embed, "Games"),
+        "• **Hades** 3h1m\n• **Celeste** 1m30s\n"
+    );
+    assert_eq!(field(embed, "Total"), "3h2m30s across 2 games");
+    assert!(embed["footer"].is_null());
+}
+
+#[tokio::test]
+async fn played_paginates() {
+    let games: Vec<_> = (0..20).map(|i| (format!("game {:02}", i), 60)).collect();
+    let games: Vec<_> = games.iter().map(|(n, s)| (n.as_str(), *s)).collect();
+    let h = played_harness(&games).await;
+
+    h.say(42, "rt.played --sort name --page 2");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    let lines: Vec<_> = field(embed, "Games").lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "• **game 15** 1m");
+    assert_eq!(field(embed, "Total"), "20m across 20 games");
+    assert_eq!(embed["footer"]["text"], "Page 2/2");
+    h.take();
+
+    h.say(42, "rt.played --page 3");
+    let reqs = h.requests(1).await;
+    assert!(reqs[0]
+        .content()
+        .unwrap()
+        .contains("expected a number from 1 to 2, got `3`"));
+}
+
+#[tokio::test]
+async fn played_validates_options() {
+    let h = played_harness(&[("Celeste", 90)]).await;
+
+    h.say(42, "rt.played --sort size");
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    assert!(content.starts_with("Invalid argument"));
+    assert!(content.contains("got `size`"));
+}
+
+#[tokio::test]
+async fn played_top_ranks_members() {
+    let h = played_harness(&[("Celeste", 90)]).await;
+
+    h.say(42, "rt.played top");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Most played games");
+    assert_eq!(embed["description"], "Across 2 members");
+    assert_eq!(
+        field(embed, "1. Celeste"),
+        "1m30s in total. Top players: <@42> 1m30s"
+    );
+}
+
+#[tokio::test]
+async fn played_top_without_entries() {
+    let h = played_harness(&[]).await;
+
+    h.say(42, "rt.played top");
+    let reqs = h.requests(1).await;
+    assert!(reqs[0].is_message_to(CHANNEL_ID));
+    assert_eq!(
+        reqs[0].content(),
+        Some("Nobody here has played anything yet")
+    );
+}
+
+#[tokio::test]
+async fn logs_message_updates() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| r.register_command(logs)).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.logs message enable");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Only bot owners may use this command.")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.logs message enable");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Enabled message logs in general"));
+    h.take();
+
+    let msg = h.say(42, "before");
+    // Wait for the message to be stored before editing it.
+    h.settle().await;
+    h.message_update(message_update(msg.id.0, "after"));
+
+    // The first log creates a webhook and posts through it.
+    let reqs = h.requests(3).await;
+    assert!(reqs[1].is_webhook_create(CHANNEL_ID));
+    assert!(reqs[2].is_webhook_execute());
+    assert_eq!(reqs[2].body["username"], "Message Logs");
+    let embed = reqs[2].embeds()[0];
+    assert_eq!(embed["title"], "Message Update");
+    assert_eq!(embed["fields"][2]["value"], "before");
+    assert_eq!(embed["fields"][3]["value"], "after");
+    h.take();
+
+    // Later ones reuse it, and recreate it if it was deleted.
+    h.delete_webhooks();
+    h.message_delete(message_delete(msg.id.0));
+    let reqs = h.requests(4).await;
+    assert!(reqs[0].is_webhook_execute());
+    assert!(reqs[2].is_webhook_create(CHANNEL_ID));
+    assert!(reqs[3].is_webhook_execute());
+    assert_eq!(reqs[3].embeds()[0]["title"], "Message Deleted");
+    assert!(!reqs.iter().any(|r| r.is_message_to(CHANNEL_ID)));
+}
+
+#[tokio::test]
+async fn logs_fall_back_to_messages_without_webhooks() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| r.register_command(logs)).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+    h.deny_webhooks();
+
+    h.say(OWNER_ID, "rt.logs message enable");
+    h.requests(1).await;
+    h.take();
+
+    let msg = h.say(42, "before");
+    h.settle().await;
+    h.message_update(message_update(msg.id.0, "after"));
+
+    let reqs = h.settle().await;
+    let sent = reqs
+        .iter()
+        .find(|r| r.is_message_to(CHANNEL_ID))
+        .expect("log message");
+    assert_eq!(sent.embeds()[0]["title"], "Message Update");
+    assert!(!reqs.iter().any(|r| r.is_webhook_execute()));
+}
+
+#[tokio::test]
+async fn logs_fall_back_to_messages_when_webhooks_are_revoked() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| r.register_command(logs)).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.logs message enable");
+    h.requests(1).await;
+    let msg = h.say(42, "before");
+    h.settle().await;
+    h.message_update(message_update(msg.id.0, "after"));
+    assert!(h.settle().await.iter().any(|r| r.is_webhook_execute()));
+    h.take();
+
+    // The webhook is gone and can't be recreated.
+    h.delete_webhooks();
+    h.deny_webhooks();
+    h.message_update(message_update(msg.id.0, "again"));
+
+    let reqs = h.settle().await;
+    let sent = reqs
+        .iter()
+        .find(|r| r.is_message_to(CHANNEL_ID))
+        .expect("log message");
+    assert_eq!(sent.embeds()[0]["title"], "Message Update");
+}
+
+#[tokio::test]
+async fn concurrent_logs_share_one_webhook() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| r.register_command(logs)).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.logs message enable");
+    h.requests(1).await;
+
+    let msgs: Vec<_> = (0..4)
+        .map(|i| h.say(42, &format!("message {}", i)))
+        .collect();
+    h.settle().await;
+    h.take();
+    for msg in &msgs {
+        h.message_delete(message_delete(msg.id.0));
+    }
+
+    let reqs = h.settle().await;
+    let created = reqs
+        .iter()
+        .filter(|r| r.is_webhook_create(CHANNEL_ID))
+        .count();
+    assert_eq!(created, 1);
+    assert_eq!(reqs.iter().filter(|r| r.is_webhook_execute()).count(), 4);
+}
+
+#[tokio::test]
+async fn logs_subcommand_errors_and_help() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| {
+        r.register_command(logs);
+        r.register_command(Help {});
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.logs message enabel");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Unknown option `enabel`, did you mean `enable`?")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.logs message enable general");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some(
+            "Invalid argument `<channel>`: expected a channel mention or id, got `general`.\n\
+             Usage: `rt.log message enable [channel]`"
+        )
+    );
+    h.take();
+
+    h.say(42, "rt.help logs message");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "rt.log message");
+    assert_eq!(embed["description"], "Log edited and deleted messages");
+    assert_eq!(field(embed, "Usage"), "`rt.log message`");
+    assert_eq!(field(embed, "Aliases"), "`messages`");
+    let subcommands = field(embed, "Subcommands");
+    assert!(subcommands.contains("`enable` `[channel]`"));
+    assert!(subcommands.contains("`disable`"));
+    assert_eq!(field(embed, "Requires"), "Server only, Bot owner");
+}
+
+#[tokio::test]
+async fn help_lists_only_usable_commands() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+        r.register_command(Settings::new());
+        r.register_command(Commands::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.help");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(field(embed, "Moderation"), "`settings`");
+    assert_eq!(field(embed, "Owner"), "`commands`");
+    h.take();
+
+    h.say(OWNER_ID, "rt.commands disable ping");
+    h.requests(1).await;
+    h.take();
+
+    h.say(42, "rt.help");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(field(embed, "General"), "`help`");
+    let sections: Vec<_> = embed["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(sections, ["General"]);
+}
+
+#[tokio::test]
+async fn help_can_be_sent_to_dms() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+        r.register_command(Settings::new());
+        r.register_command(Commands::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.help --dm");
+    let reqs = h.requests(3).await;
+    assert_eq!(reqs[0].path, "/users/@me/channels");
+    assert!(reqs[1].is_message_to(DM_CHANNEL_ID));
+    let embed = reqs[1].embeds()[0];
+    assert!(field(embed, "General").contains("`ping`"));
+    // Commands needing permissions the user lacks here are still listed, but
+    // owner commands never are.
+    assert_eq!(field(embed, "Moderation"), "`settings`");
+    assert!(!embed["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|f| f["name"] == "Owner"));
+    assert!(reqs[2].is_message_to(CHANNEL_ID));
+    assert_eq!(reqs[2].content(), Some("Sent you a DM with the help"));
+}
+
+/// Only has help, and only works in DMs.
+struct DmOnly;
+
+#[async_trait]
+impl Command for DmOnly {
+    fn name(&self) -> &'static str {
+        "whisper"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "whisper",
+            requires: vec![Requirement::Dm],
+            ..Default::default()
+        }]
+    }
+}
+
+#[tokio::test]
+async fn help_lists_dm_commands_outside_guilds() {
+    let h = Harness::new(|r| {
+        r.register_command(DmOnly);
+        r.register_command(Help {});
+    })
+    .await;
+
+    h.say(42, "rt.help");
+    let reqs = h.requests(1).await;
+    assert!(!field(reqs[0].embeds()[0], "General").contains("`whisper`"));
+    h.take();
+
+    h.dm(42, "rt.help");
+    let reqs = h.requests(1).await;
+    assert!(reqs[0].is_message_to(DM_CHANNEL_ID));
+    assert!(field(reqs[0].embeds()[0], "General").contains("`whisper`"));
+    h.take();
+
+    h.say(42, "rt.help --dm");
+    let reqs = h.requests(3).await;
+    assert!(reqs[1].is_message_to(DM_CHANNEL_ID));
+    assert!(field(reqs[1].embeds()[0], "General").contains("`whisper`"));
+    h.take();
+
+    h.dm(42, "rt.help whisper");
+    let reqs = h.requests(1).await;
+    assert_eq!(field(reqs[0].embeds()[0], "Requires"), "DMs only");
+}
+
+#[tokio::test]
+async fn help_describes_a_command() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+        r.register_command(Settings::new());
+    })
+    .await;
+
+    h.say(42, "rt.help ping");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "rt.ping");
+    assert!(embed["description"]
+        .as_str()
+        .unwrap()
+        .starts_with("Checks that the bot is alive\n\n"));
+    assert_eq!(field(embed, "Usage"), "`rt.ping`");
+    assert_eq!(field(embed, "Examples"), "`rt.ping`");
+    h.take();
+
+    // Aliases lead to the same page.
+    h.say(42, "rt.help config lang");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "rt.settings language");
+    assert_eq!(field(embed, "Usage"), "`rt.settings language [language]`");
+    assert_eq!(field(embed, "Aliases"), "`lang`");
+    assert_eq!(field(embed, "Requires"), "Server only, Manage Guild");
+    h.take();
+
+    h.say(42, "rt.help config langauge");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Unknown option `langauge`, did you mean `language`?")
+    );
+}
+
+#[tokio::test]
+async fn help_is_localized() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+        r.register_command(Settings::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.settings language de");
+    h.requests(1).await;
+    h.take();
+
+    h.say(42, "rt.help ping");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert!(embed["description"]
+        .as_str()
+        .unwrap()
+        .starts_with("Prüft, ob der Bot noch lebt\n\nZeigt, wie schnell"));
+    assert_eq!(field(embed, "Verwendung"), "`rt.ping`");
+    h.take();
+
+    h.say(OWNER_ID, "rt.help settings");
+    let reqs = h.requests(1).await;
+    assert!(field(reqs[0].embeds()[0], "Unterbefehle")
+        .contains("`language` `[language]` - Die Sprache, in der der Bot antwortet"));
+    h.take();
+
+    // Searches match the translated descriptions.
+    h.say(42, "rt.help search lebt");
+    let reqs = h.requests(1).await;
+    let results = reqs[0].embeds()[0]["description"].as_str().unwrap();
+    assert_eq!(results, "`rt.ping` - Prüft, ob der Bot noch lebt");
+}
+
+/// Only has help, with a cooldown.
+struct Slow;
+
+#[async_trait]
+impl Command for Slow {
+    fn name(&self) -> &'static str {
+        "slow"
+    }
+
+    fn help(&self, _: Option<&Message>) -> Vec<CommandHelp> {
+        vec![CommandHelp {
+            name: "slow",
+            cooldown: Some(Duration::from_secs(5)),
+            ..Default::default()
+        }]
+    }
+}
+
+#[tokio::test]
+async fn help_shows_cooldowns() {
+    let h = Harness::new(|r| {
+        r.register_command(Slow);
+        r.register_command(Help {});
+    })
+    .await;
+
+    h.say(42, "rt.help slow");
+    let reqs = h.requests(1).await;
+    assert_eq!(field(reqs[0].embeds()[0], "Cooldown"), "5 seconds");
+    h.take();
+
+    // Commands without one don't get the field.
+    h.say(42, "rt.help help");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert!(embed["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|f| f["name"] != "Cooldown"));
+}
+
+#[tokio::test]
+async fn help_search_ranks_commands() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+        r.register_command(logs);
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(
+        OWNER_ID,
+        "rt.help search the thing that logs deleted messages",
+    );
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    let results = embed["description"].as_str().unwrap();
+    assert!(results.starts_with("`rt.log message` - Log edited and deleted messages"));
+    assert!(!results.contains("rt.ping"));
+    h.take();
+
+    // Subcommands the user can't run aren't found.
+    h.say(42, "rt.help search deleted messages");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("No commands match \"deleted messages\"")
+    );
+}
+
+#[tokio::test]
+async fn say_repeats_text_safely() {
+    let h = Harness::new(|r| r.register_command(Say {})).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.say hi");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("You're missing the following permissions: Manage Messages")
+    );
+    h.take();
+
+    let msg = h.say(
+        OWNER_ID,
+        &format!("rt.say <#{}> <@42> hi --delete", CHANNEL_ID),
+    );
+    let reqs = h.requests(2).await;
+    assert!(reqs[0].is_message_to(CHANNEL_ID));
+    assert_eq!(reqs[0].content(), Some("<@42> hi"));
+    assert!(reqs[0].body["allowed_mentions"].is_object());
+    assert_eq!(reqs[1].method, Method::DELETE);
+    assert_eq!(
+        reqs[1].path,
+        format!("/channels/{}/messages/{}", CHANNEL_ID, msg.id)
+    );
+}
+
+#[tokio::test]
+async fn say_builds_embeds() {
+    let h = Harness::new(|r| r.register_command(Say {})).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(
+        OWNER_ID,
+        "rt.say --embed title: Rules\ndescription: Be nice\nfield: One: No spam",
+    );
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Rules");
+    assert_eq!(embed["description"], "Be nice");
+    assert_eq!(field(embed, "One"), "No spam");
+    h.take();
+
+    h.say(
+        OWNER_ID,
+        r#"rt.say --embed {"title": "Hi", "fields": [{"name": "a", "value": "b"}]}"#,
+    );
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Hi");
+    assert_eq!(field(embed, "a"), "b");
+    h.take();
+
+    h.say(
+        OWNER_ID,
+        "rt.say --embed title: Rules --delete\ndescription: Be nice",
+    );
+    let reqs = h.requests(2).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Rules");
+    assert_eq!(embed["description"], "Be nice");
+    assert_eq!(reqs[1].method, Method::DELETE);
+    h.take();
+
+    h.say(
+        OWNER_ID,
+        &format!("rt.say --embed nonsense @everyone {}", "a".repeat(3000)),
+    );
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    assert!(content.starts_with(
+        "Invalid argument `<text...>`: expected an embed as JSON or `key: value` lines.\n"
+    ));
+    assert!(!content.contains("@everyone"));
+}
+
+#[tokio::test]
+async fn stats_counts_guilds_and_commands() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Stats {});
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.ping");
+    h.settle().await;
+    h.take();
+
+    h.say(42, "rt.stats");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Bot statistics");
+    assert_eq!(field(embed, "Servers"), "1");
+    assert_eq!(field(embed, "Users"), "2");
+    assert_eq!(field(embed, "Commands run"), "1");
+    assert!(field(embed, "Version").starts_with(env!("CARGO_PKG_VERSION")));
+}
+
+#[tokio::test]
+async fn userinfo_shows_membership() {
+    let h = Harness::new(|r| r.register_command(UserInfo {})).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.userinfo");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "user42#0001");
+    assert_eq!(
+        embed["thumbnail"]["url"],
+        "https://cdn.discordapp.com/embed/avatars/1.png"
+    );
+    assert_eq!(field(embed, "ID"), "42");
+    // Ids this small were created at the very start of Discord's epoch.
+    assert!(field(embed, "Created").starts_with("January 1, 2015 ("));
+    assert!(field(embed, "Joined").starts_with("January 1, 2020 ("));
+    assert_eq!(field(embed, "Join position"), "#1 of 2");
+    assert_eq!(field(embed, "Roles (0)"), "None");
+    h.take();
+
+    h.say(42, "rt.userinfo nobody");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some(
+            "Invalid argument `[user]`: expected a user mention or id, got `nobody`.\n\
+             Usage: `rt.userinfo [user]`"
+        )
+    );
+}
+
+#[tokio::test]
+async fn bad_arguments_are_shortened_and_dont_ping() {
+    let h = Harness::new(|r| r.register_command(UserInfo {})).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(
+        42,
+        &format!("rt.userinfo \"`@everyone` {}\"", "a".repeat(3000)),
+    );
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    assert!(content.starts_with(
+        "Invalid argument `[user]`: expected a user mention or id, got `'@everyone' aaa"
+    ));
+    assert!(content.contains("a…`."));
+    assert!(content.chars().count() < 300);
+    assert!(reqs[0].body["allowed_mentions"].is_object());
+}
+
+#[tokio::test]
+async fn server_role_and_channel_info() {
+    let h = Harness::new(|r| {
+        r.register_command(ServerInfo {});
+        r.register_command(RoleInfo {});
+        r.register_command(ChannelInfo {});
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.serverinfo");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "test guild");
+    assert_eq!(field(embed, "Owner"), format!("<@{}>", OWNER_ID));
+    assert_eq!(field(embed, "Members"), "2");
+    assert_eq!(field(embed, "Channels"), "1");
+    h.take();
+
+    h.say(42, "rt.channelinfo");
+    let reqs = h.requests(1).await;
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "general");
+    assert_eq!(field(embed, "Type"), "Text");
+    assert_eq!(field(embed, "Slowmode"), "None");
+    h.take();
+
+    // Roles the cache doesn't know about are looked up before giving up.
+    h.say(42, "rt.roleinfo <@&555>");
+    let reqs = h.requests(2).await;
+    assert!(reqs[0].method == Method::GET && reqs[0].path == format!("/guilds/{}/roles", GUILD_ID));
+    assert_eq!(
+        reqs[1].content(),
+        Some(
+            "Invalid argument `<role>`: expected a role in this server, got `<@&555>`.\n\
+             Usage: `rt.roleinfo <role>`"
+        )
+    );
+}
+
+#[tokio::test]
+async fn avatar_falls_back_to_the_api() {
+    let h = Harness::new(|r| r.register_command(Avatar {})).await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.avatar 77");
+    let reqs = h.requests(2).await;
+    assert!(reqs[0].method == Method::GET && reqs[0].path == "/users/77");
+    let embed = reqs[1].embeds()[0];
+    assert_eq!(embed["title"], "Avatar of user77#0001");
+    assert_eq!(
+        embed["image"]["url"],
+        "https://cdn.discordapp.com/embed/avatars/1.png?size=1024"
+    );
+}
+
+#[tokio::test]
+async fn forwarded_events_reach_commands() {
+    let h = Harness::new(|r| r.register_command(Ping {})).await;
+
+    let msg = message(1, 42, "rt.ping");
+    let event = Event::MessageCreate(Box::new(MessageCreate(msg)));
+    h.bot.forward_events(1, futures::stream::iter(vec![event]));
+
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+}
+
+#[tokio::test]
+async fn maintenance_mode_only_lets_owners_in() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Control::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.bot maintenance");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Only bot owners may use this command.")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.bot maintenance");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Maintenance mode is on, only owners can use commands")
+    );
+    h.take();
+
+    // Only the first command in a while is answered.
+    h.say(42, "rt.ping");
+    h.say(42, "rt.ping");
+    h.say(42, "just chatting");
+    let reqs = h.settle().await;
+    assert_eq!(reqs.len(), 1);
+    assert_eq!(
+        reqs[0].content(),
+        Some("The bot is down for maintenance, try again later.")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.ping");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+    h.settle().await;
+    h.take();
+
+    h.say(OWNER_ID, "rt.bot maintenance off");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Maintenance mode is off"));
+    h.take();
+
+    h.say(42, "rt.ping");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+}
+
+#[tokio::test]
+async fn control_commands_reply_and_shut_down() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Control::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.bot restart 5");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("There's no shard 5"));
+    h.take();
+
+    h.say(OWNER_ID, "rt.bot reload");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Reloaded the configuration and settings for 1 server")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.bot shutdown");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Shutting down..."));
+    h.settle().await;
+    assert!(h.stopped());
+}
+
+#[tokio::test]
+async fn presences_are_filled_from_the_cache() {
+    let h = Harness::new(|r| {
+        r.register_command(Control::new());
+        r.set_presence_templates(vec![
+            "{prefix}help | {guilds} servers, {users} users in {channels} channels".into(),
+        ]);
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.bot rotate");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Rotating presences again, now showing `rt.help | 1 servers, 2 users in 1 channels`")
+    );
+}
+
+#[tokio::test]
+async fn reload_applies_new_presences() {
+    let config = Arc::new(std::sync::Mutex::new(Config::default()));
+    let loaded = config.clone();
+    let h = Harness::new(move |r| {
+        r.register_command(Control::new());
+        r.set_config_loader(move || Ok(loaded.lock().unwrap().clone()));
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    config.lock().unwrap().presences = Some(vec!["{prefix}help | reloaded".into()]);
+    h.say(OWNER_ID, "rt.bot reload");
+    h.requests(1).await;
+    h.take();
+
+    h.say(OWNER_ID, "rt.bot rotate");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Rotating presences again, now showing `rt.help | reloaded`")
+    );
+}
+
+/// Returns the value of the embed field called `name`.
+fn field<'a>(embed: &'a serde_json::Value, name: &str) -> &'a str {
+    embed["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == name)
+        .and_then(|f| f["value"].as_str())
+        .unwrap_or_else(|| panic!("no {} field", name))
+}
+
+#[tokio::test]
+async fn suggests_close_commands_once_per_channel() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Help {});
+    })
+    .await;
+
+    h.say(42, "rt.pign");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Unknown command `pign`, did you mean `ping`?")
+    );
+    h.take();
+
+    // Close enough to suggest `help`, but the channel is cooling down.
+    h.say(42, "rt.hepl");
+    h.say(42, "rt.nothing like any command");
+    assert!(h.settle().await.is_empty());
+}
+
+#[tokio::test]
+async fn suggestions_can_be_disabled_per_guild() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Settings::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.settings suggestions off");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("You're missing the following permissions: Manage Guild")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.settings suggestions off");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Command suggestions are off"));
+    h.take();
+
+    h.say(42, "rt.pign");
+    assert!(h.settle().await.is_empty());
+}
+
+#[tokio::test]
+async fn settings_language_localizes_replies() {
+    let h = Harness::new(|r| {
+        r.register_command(Settings::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.settings language de");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Die Sprache dieses Servers ist Deutsch (`de`)")
+    );
+    h.take();
+
+    h.say(42, "rt.settings suggestions off");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Dir fehlen folgende Berechtigungen: Manage Guild")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.settings language xx");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Unbekannte Sprache `xx`. Verfügbare Sprachen: `en-US`, `de`")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.settings lang en");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("This server's language is English (`en-US`)")
+    );
+}
+
+#[tokio::test]
+async fn owners_can_disable_and_enable_commands() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Commands::new());
+    })
+    .await;
+
+    h.say(42, "rt.commands disable ping");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("Only bot owners may use this command.")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.commands disable ping");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Disabled `ping`"));
+    h.take();
+
+    h.say(42, "rt.ping");
+    assert!(h.settle().await.is_empty());
+
+    h.say(OWNER_ID, "rt.cmds list");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("**Commands**\n• `ping` - disabled\n• `commands` - enabled")
+    );
+    h.take();
+
+    h.say(OWNER_ID, "rt.commands disable commands");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("`commands` can't be disabled"));
+    h.take();
+
+    h.say(OWNER_ID, "rt.commands enable ping");
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Enabled `ping`"));
+    h.take();
+
+    h.say(42, "rt.ping");
+    let reqs = h.requests(2).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+}
+
+#[tokio::test]
+async fn commands_can_be_registered_while_running() {
+    let h = Harness::new(|_| {}).await;
+
+    h.bot.register_command(Ping {});
+    h.say(42, "rt.ping");
+    let reqs = h.requests(2).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+    h.take();
+
+    assert!(h.bot.unregister_command("ping"));
+    h.say(42, "rt.ping");
+    assert!(h.settle().await.is_empty());
+}
+
+#[tokio::test]
+async fn audit_records_invocations() {
+    let h = Harness::new(|r| {
+        r.register_command(Ping {});
+        r.register_command(Audit::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(42, "rt.audit guild");
+    let reqs = h.requests(1).await;
+    assert_eq!(
+        reqs[0].content(),
+        Some("You're missing the following permissions: Administrator")
+    );
+    h.take();
+
+    h.say(42, "rt.ping");
+    h.requests(2).await;
+    h.say(42, "rt.ping extra args");
+    h.requests(4).await;
+    h.settle().await;
+    h.take();
+
+    h.say(OWNER_ID, "rt.audit guild --limit 2");
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines[0], "**Recent commands**");
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].contains("user42#0001 ran `ping extra args` - ok"));
+    assert!(lines[2].contains("user42#0001 ran `ping` - ok"));
+    h.take();
+
+    h.say(OWNER_ID, "rt.audit user 42 --limit 1");
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    assert_eq!(content.lines().count(), 2);
+    assert!(content.contains("ran `ping extra args`"));
+    h.take();
+
+    // Being turned away is recorded as such.
+    h.say(OWNER_ID, "rt.audit user 42 --limit 3");
+    let reqs = h.requests(1).await;
+    let content = reqs[0].content().unwrap();
+    assert!(content
+        .lines()
+        .nth(3)
+        .unwrap()
+        .contains("ran `audit guild` - denied"));
+}
+
+#[tokio::test]
+async fn logs_receive_published_events() {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let storage = MemoryStorage::new();
+    let logs = Logs::new(Arc::new(storage.clone())).await.unwrap();
+    let h = Harness::with_storage(storage, move |r| {
+        r.register_command(logs);
+        r.register_command(Settings::new());
+    })
+    .await;
+    h.send(guild_create(&[OWNER_ID, 42]));
+
+    h.say(OWNER_ID, "rt.logs message enable");
+    h.requests(1).await;
+    h.take();
+
+    h.say(OWNER_ID, "rt.settings suggestions off");
+    let reqs = h.requests(4).await;
+    let exec = reqs.iter().find(|r| r.is_webhook_execute()).unwrap();
+    let embed = exec.embeds()[0];
+    assert_eq!(embed["title"], "Setting Changed");
+    assert_eq!(embed["fields"][1]["value"], "suggestions");
+    assert_eq!(embed["fields"][2]["value"], "off");
+    h.take();
+
+    h.bot.publish(BotEvent::ModerationAction(ModerationAction {
+        guild_id: GuildId(GUILD_ID),
+        moderator_id: UserId(OWNER_ID),
+        target_id: UserId(42),
+        kind: ModerationKind::Ban,
+        reason: Some("spam".to_owned()),
+    }));
+    let reqs = h.requests(1).await;
+    assert!(reqs[0].is_webhook_execute());
+    let embed = reqs[0].embeds()[0];
+    assert_eq!(embed["title"], "Member Banned");
+    assert_eq!(embed["fields"][1]["value"], "<@42> user42#0001 42");
+    assert_eq!(embed["fields"][2]["value"], "spam");
+}
+
+/// Counts the bot events it receives.
+struct Counter(Arc<AtomicUsize>);
+
+#[async_trait]
+impl Command for Counter {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn subscribes(&self) -> &'static [BotEventKind] {
+        &[BotEventKind::CommandToggled]
+    }
+
+    async fn receive_event(&self, _: &Rikka, _: &BotEvent) -> Result<(), CommandError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn bot_events_only_reach_subscribers() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let counter = Counter(count.clone());
+    let h = Harness::new(move |r| r.register_command(counter)).await;
+
+    h.bot.publish(BotEvent::ModerationAction(ModerationAction {
+        guild_id: GuildId(GUILD_ID),
+        moderator_id: UserId(OWNER_ID),
+        target_id: UserId(42),
+        kind: ModerationKind::Kick,
+        reason: None,
+    }));
+    h.bot.publish(BotEvent::CommandToggled {
+        name: "ping",
+        enabled: false,
+    });
+    h.settle().await;
+
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+/// Never finishes handling `rt.block`, and only handles one at a time.
+struct Block;
+
+#[async_trait]
+impl Command for Block {
+    fn name(&self) -> &'static str {
+        "block"
+    }
+
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    async fn receive(&self, _: &Rikka, msg: &Message) -> CommandResult {
+        if msg.content != "rt.block" {
+            return Err(CommandError::NoMatch);
+        }
+
+        futures::future::pending().await
+    }
+
+    fn latency<'a>(&'a self, _: &'a Rikka) -> Option<(&'static str, LatencyProbe<'a>)> {
+        Some(("ping-object-storage", Box::pin(futures::future::pending())))
+    }
+}
+
+#[tokio::test]
+async fn busy_commands_dont_starve_others() {
+    let h = Harness::new(|r| {
+        r.set_concurrency_limit(2);
+        r.register_command(Block);
+        r.register_command(Ping {});
+    })
+    .await;
+
+    for _ in 0..4 {
+        h.say(42, "rt.block");
+    }
+    h.say(42, "rt.ping");
+
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+}
+
+/// Handles `rt.gate` once the test lets it through.
+struct Gate(Arc<Semaphore>);
+
+#[async_trait]
+impl Command for Gate {
+    fn name(&self) -> &'static str {
+        "gate"
+    }
+
+    async fn receive(&self, _: &Rikka, msg: &Message) -> CommandResult {
+        if msg.content != "rt.gate" {
+            return Err(CommandError::NoMatch);
+        }
+
+        self.0.acquire().await.forget();
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn saturated_loop_stops_reading_events() {
+    let gate = Arc::new(Semaphore::new(0));
+    let cmd = Gate(gate.clone());
+    let h = Harness::new(move |r| {
+        r.set_concurrency_limit(1);
+        r.register_command(cmd);
+        r.register_command(Ping {});
+    })
+    .await;
+
+    h.say(42, "rt.gate");
+    h.say(42, "rt.ping");
+    assert!(h.settle().await.is_empty());
+
+    // The gate holds the only slot, and the loop is waiting for it to spawn
+    // the next handler for the same message, so it never read the ping.
+    let (_, global) = h.bot.concurrency_stats()[0];
+    assert_eq!((global.running, global.queued), (1, 1));
+
+    gate.add_permits(1);
+    let reqs = h.requests(1).await;
+    assert_eq!(reqs[0].content(), Some("Pong!"));
+}
@@ -0,0 +1,528 @@
+//! An in-process stand-in for Discord.
+//!
+//! [`Harness`] runs a [`Rikka`] whose HTTP client is pointed at a local server
+//! that records every request and answers with plausible payloads, and whose
+//! gateway events are fed from a channel the test controls.
+#![allow(dead_code)]
+
+use futures::channel::mpsc::{self, UnboundedSender};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use twilight_gateway::cluster::{Cluster, ShardScheme};
+use twilight_gateway::Event;
+use twilight_http::Client as HttpClient;
+use twilight_model::channel::Message;
+use twilight_model::gateway::payload::{GuildCreate, MessageCreate, MessageDelete, MessageUpdate};
+use twilight_model::gateway::Intents;
+use twilight_model::id::{ChannelId, UserId};
+
+use rikka_rs::storage::MemoryStorage;
+use rikka_rs::Rikka;
+
+pub const PREFIX: &str = "rt.";
+pub const BOT_ID: u64 = 1;
+pub const OWNER_ID: u64 = 105484726235607040;
+pub const GUILD_ID: u64 = 100;
+pub const CHANNEL_ID: u64 = 200;
+/// The DM channel opened with any user.
+pub const DM_CHANNEL_ID: u64 = 300;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request the bot made against the fake API.
+#[derive(Clone, Debug)]
+pub struct Recorded {
+    pub method: Method,
+    pub path: String,
+    pub body: Value,
+}
+
+impl Recorded {
+    pub fn content(&self) -> Option<&str> {
+        self.body["content"].as_str()
+    }
+
+    /// Returns every embed attached to the request, whether it was sent as a
+    /// single `embed` or a list of `embeds`.
+    pub fn embeds(&self) -> Vec<&Value> {
+        let mut embeds = Vec::new();
+        if self.body["embed"].is_object() {
+            embeds.push(&self.body["embed"]);
+        }
+        if let Some(list) = self.body["embeds"].as_array() {
+            embeds.extend(list.iter());
+        }
+
+        embeds
+    }
+
+    pub fn is_message_to(&self, channel: u64) -> bool {
+        self.method == Method::POST && self.path == format!("/channels/{}/messages", channel)
+    }
+
+    pub fn is_webhook_create(&self, channel: u64) -> bool {
+        self.method == Method::POST && self.path == format!("/channels/{}/webhooks", channel)
+    }
+
+    pub fn is_webhook_execute(&self) -> bool {
+        self.method == Method::POST && self.path.starts_with("/webhooks/")
+    }
+}
+
+#[derive(Default)]
+struct FakeDiscord {
+    requests: Mutex<Vec<Recorded>>,
+    next_id: AtomicU64,
+    /// Webhook ids mapped to their channel and token.
+    webhooks: Mutex<HashMap<u64, (String, String)>>,
+    /// Whether listing and creating webhooks fails, as if the bot were
+    /// missing Manage Webhooks.
+    webhooks_denied: AtomicBool,
+}
+
+impl FakeDiscord {
+    fn snowflake(&self) -> u64 {
+        // Stay well clear of the ids tests use for their own fixtures.
+        1_000_000 + self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+pub struct Harness {
+    pub bot: Arc<Rikka>,
+    pub storage: MemoryStorage,
+    events: UnboundedSender<(u64, Event)>,
+    state: Arc<FakeDiscord>,
+}
+
+impl Harness {
+    /// Boots a bot against the fake API, backed by fresh in-memory storage.
+    /// `setup` runs before the bot starts and is where tests register the
+    /// commands under test.
+    pub async fn new<F: FnOnce(&mut Rikka)>(setup: F) -> Harness {
+        Harness::with_storage(MemoryStorage::new(), setup).await
+    }
+
+    /// Like [`Harness::new`], but shares `storage` with the bot so commands
+    /// can be constructed against it up front.
+    pub async fn with_storage<F: FnOnce(&mut Rikka)>(storage: MemoryStorage, setup: F) -> Harness {
+        let state = Arc::new(FakeDiscord::default());
+        let addr = serve(state.clone());
+
+        let http = HttpClient::builder()
+            .token("fake-token")
+            .proxy(addr.to_string(), true)
+            .build()
+            .expect("build http client");
+        let cluster = Cluster::builder("fake-token", Intents::all())
+            .shard_scheme(ShardScheme::Range {
+                from: 0,
+                to: 0,
+                total: 1,
+            })
+            .http_client(http.clone())
+            .build()
+            .await
+            .expect("build cluster");
+
+        let mut bot = Rikka::from_parts(cluster, http, Arc::new(storage.clone()));
+        bot.register_prefix(PREFIX);
+        bot.register_owner(UserId(OWNER_ID));
+        setup(&mut bot);
+
+        let bot = Arc::new(bot);
+        let (events, rx) = mpsc::unbounded();
+        tokio::spawn(bot.clone().run(rx));
+
+        Harness {
+            bot,
+            storage,
+            events,
+            state,
+        }
+    }
+
+    pub fn send(&self, event: Event) {
+        self.events
+            .unbounded_send((0, event))
+            .expect("event loop stopped");
+    }
+
+    /// Whether the bot stopped handling events, e.g. after shutting down.
+    pub fn stopped(&self) -> bool {
+        self.events.is_closed()
+    }
+
+    pub fn message_create(&self, msg: Message) {
+        self.send(Event::MessageCreate(Box::new(MessageCreate(msg))));
+    }
+
+    pub fn message_update(&self, update: MessageUpdate) {
+        self.send(Event::MessageUpdate(Box::new(update)));
+    }
+
+    pub fn message_delete(&self, delete: MessageDelete) {
+        self.send(Event::MessageDelete(delete));
+    }
+
+    /// Sends `content` as a message from `author` in the default guild
+    /// channel.
+    pub fn say(&self, author: u64, content: &str) -> Message {
+        let msg = message(self.state.snowflake(), author, content);
+        self.message_create(msg.clone());
+        msg
+    }
+
+    /// Sends `content` as a DM from `author`.
+    pub fn dm(&self, author: u64, content: &str) -> Message {
+        let mut msg = message(self.state.snowflake(), author, content);
+        msg.channel_id = ChannelId(DM_CHANNEL_ID);
+        msg.guild_id = None;
+        self.message_create(msg.clone());
+        msg
+    }
+
+    /// Waits until the bot has made at least `n` requests and returns all of
+    /// them. Panics if that doesn't happen in time.
+    pub async fn requests(&self, n: usize) -> Vec<Recorded> {
+        let start = std::time::Instant::now();
+        loop {
+            {
+                let requests = self.state.requests.lock().unwrap();
+                if requests.len() >= n {
+                    return requests.clone();
+                }
+            }
+
+            if start.elapsed() > TIMEOUT {
+                panic!(
+                    "expected {} requests, got {:#?}",
+                    n,
+                    self.state.requests.lock().unwrap()
+                );
+            }
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Gives in-flight handlers a moment to finish and returns every request
+    /// made so far. Use this when asserting that nothing was sent.
+    pub async fn settle(&self) -> Vec<Recorded> {
+        tokio::time::delay_for(Duration::from_millis(250)).await;
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Deletes every webhook, as if someone removed them in the channel
+    /// settings.
+    pub fn delete_webhooks(&self) {
+        self.state.webhooks.lock().unwrap().clear();
+    }
+
+    /// Makes listing and creating webhooks fail from now on.
+    pub fn deny_webhooks(&self) {
+        self.state.webhooks_denied.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the requests recorded so far and forgets them.
+    pub fn take(&self) -> Vec<Recorded> {
+        std::mem::take(&mut *self.state.requests.lock().unwrap())
+    }
+}
+
+fn serve(state: Arc<FakeDiscord>) -> SocketAddr {
+    let make_svc = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+    });
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    addr
+}
+
+async fn handle(state: Arc<FakeDiscord>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = strip_api_prefix(req.uri().path()).to_owned();
+    let raw = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+    let body: Value = serde_json::from_slice(&raw).unwrap_or(Value::Null);
+
+    state.requests.lock().unwrap().push(Recorded {
+        method: method.clone(),
+        path: path.clone(),
+        body: body.clone(),
+    });
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if let ["channels", _, "webhooks"] = segments.as_slice() {
+        if state.webhooks_denied.load(Ordering::Relaxed) {
+            return Ok(error(StatusCode::FORBIDDEN, 50013, "Missing Permissions"));
+        }
+    }
+
+    let res = match (&method, segments.as_slice()) {
+        (&Method::POST, ["channels", cid, "messages"]) => {
+            Some(echo_message(state.snowflake(), cid, &body))
+        }
+        (&Method::PATCH, ["channels", cid, "messages", mid]) => {
+            Some(echo_message(mid.parse().unwrap_or_default(), cid, &body))
+        }
+        (&Method::DELETE, ["channels", _, "messages", _]) => Some(Value::Null),
+        (&Method::GET, ["channels", cid, "webhooks"]) => Some(Value::Array(
+            state
+                .webhooks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, (channel, _))| channel == cid)
+                .map(|(id, (channel, token))| webhook_json(*id, channel, token, &body))
+                .collect(),
+        )),
+        (&Method::POST, ["channels", cid, "webhooks"]) => {
+            let id = state.snowflake();
+            let token = format!("token{}", id);
+            state
+                .webhooks
+                .lock()
+                .unwrap()
+                .insert(id, (cid.to_string(), token.clone()));
+            Some(webhook_json(id, cid, &token, &body))
+        }
+        (&Method::POST, ["webhooks", id, token]) => {
+            let hook = state
+                .webhooks
+                .lock()
+                .unwrap()
+                .get(&id.parse().unwrap_or_default())
+                .cloned();
+            match hook {
+                Some((cid, real)) if real == *token => {
+                    Some(echo_message(state.snowflake(), &cid, &body))
+                }
+                _ => None,
+            }
+        }
+        (&Method::POST, ["users", "@me", "channels"]) => Some(json!({
+            "id": DM_CHANNEL_ID.to_string(),
+            "type": 1,
+            "last_message_id": null,
+            "recipients": [user_json(
+                body["recipient_id"]
+                    .as_str()
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or_default(),
+                false,
+            )],
+        })),
+        (&Method::GET, ["users", uid]) => Some(user_json(uid.parse().unwrap_or_default(), false)),
+        (&Method::GET, ["guilds", _, "roles"]) => Some(Value::Array(Vec::new())),
+        (&Method::GET, ["gateway", "bot"]) => Some(json!({
+            "url": "wss://gateway.invalid",
+            "shards": 1,
+            "session_start_limit": { "total": 1000, "remaining": 1000, "reset_after": 0 },
+        })),
+        _ => None,
+    };
+
+    let res = match res {
+        Some(body) => Response::new(Body::from(body.to_string())),
+        None => error(StatusCode::NOT_FOUND, 0, "404: Not Found"),
+    };
+
+    Ok(res)
+}
+
+fn error(status: StatusCode, code: u64, message: &str) -> Response<Body> {
+    let mut res = Response::new(Body::from(
+        json!({ "code": code, "message": message }).to_string(),
+    ));
+    *res.status_mut() = status;
+    res
+}
+
+fn strip_api_prefix(path: &str) -> &str {
+    match path.find("/api/v") {
+        Some(idx) => {
+            let rest = &path[idx + "/api/v".len()..];
+            rest.find('/').map(|i| &rest[i..]).unwrap_or("/")
+        }
+        None => path,
+    }
+}
+
+/// Builds the message Discord would return for a create/update request.
+fn echo_message(id: u64, channel: &str, body: &Value) -> Value {
+    let mut embeds = Vec::new();
+    if body["embed"].is_object() {
+        embeds.push(body["embed"].clone());
+    }
+
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel,
+        "author": user_json(BOT_ID, true),
+        "content": body["content"].as_str().unwrap_or_default(),
+        "timestamp": "2020-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": embeds,
+        "reactions": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+fn webhook_json(id: u64, channel: &str, token: &str, body: &Value) -> Value {
+    json!({
+        "id": id.to_string(),
+        "type": 1,
+        "guild_id": GUILD_ID.to_string(),
+        "channel_id": channel,
+        "name": body["name"].as_str().unwrap_or("Rikka"),
+        "avatar": null,
+        "token": token,
+        "user": user_json(BOT_ID, true),
+    })
+}
+
+fn user_json(id: u64, bot: bool) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": format!("user{}", id),
+        "discriminator": "0001",
+        "avatar": null,
+        "bot": bot,
+    })
+}
+
+/// A guild message from `author` in the default channel.
+pub fn message(id: u64, author: u64, content: &str) -> Message {
+    serde_json::from_value(json!({
+        "id": id.to_string(),
+        "channel_id": CHANNEL_ID.to_string(),
+        "guild_id": GUILD_ID.to_string(),
+        "author": user_json(author, author == BOT_ID),
+        "content": content,
+        "timestamp": "2020-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "reactions": [],
+        "pinned": false,
+        "type": 0,
+    }))
+    .expect("valid message")
+}
+
+pub fn message_update(id: u64, content: &str) -> MessageUpdate {
+    serde_json::from_value(json!({
+        "id": id.to_string(),
+        "channel_id": CHANNEL_ID.to_string(),
+        "guild_id": GUILD_ID.to_string(),
+        "content": content,
+    }))
+    .expect("valid message update")
+}
+
+pub fn message_delete(id: u64) -> MessageDelete {
+    serde_json::from_value(json!({
+        "id": id.to_string(),
+        "channel_id": CHANNEL_ID.to_string(),
+        "guild_id": GUILD_ID.to_string(),
+    }))
+    .expect("valid message delete")
+}
+
+/// A guild with a single text channel whose members are `members`. The first
+/// member owns the guild.
+pub fn guild_create(members: &[u64]) -> Event {
+    let members: Vec<Value> = members
+        .iter()
+        .map(|id| {
+            json!({
+                "user": user_json(*id, false),
+                "nick": null,
+                "roles": [],
+                "joined_at": "2020-01-01T00:00:00.000000+00:00",
+                "premium_since": null,
+                "deaf": false,
+                "mute": false,
+            })
+        })
+        .collect();
+
+    let guild = serde_json::from_value(json!({
+        "id": GUILD_ID.to_string(),
+        "name": "test guild",
+        "icon": null,
+        "splash": null,
+        "discovery_splash": null,
+        "owner_id": members
+            .first()
+            .map(|m| m["user"]["id"].clone())
+            .unwrap_or_else(|| OWNER_ID.to_string().into()),
+        "region": "us-west",
+        "afk_channel_id": null,
+        "afk_timeout": 300,
+        "verification_level": 0,
+        "default_message_notifications": 0,
+        "explicit_content_filter": 0,
+        "roles": [],
+        "emojis": [],
+        "features": [],
+        "mfa_level": 0,
+        "application_id": null,
+        "system_channel_id": null,
+        "system_channel_flags": 0,
+        "rules_channel_id": null,
+        "joined_at": "2020-01-01T00:00:00.000000+00:00",
+        "large": false,
+        "unavailable": false,
+        "member_count": members.len(),
+        "voice_states": [],
+        "members": members,
+        "channels": [{
+            "id": CHANNEL_ID.to_string(),
+            "guild_id": GUILD_ID.to_string(),
+            "type": 0,
+            "name": "general",
+            "position": 0,
+            "permission_overwrites": [],
+            "nsfw": false,
+            "topic": null,
+            "last_message_id": null,
+            "parent_id": null,
+            "rate_limit_per_user": 0,
+        }],
+        "presences": [],
+        "max_presences": null,
+        "max_members": 250000,
+        "vanity_url_code": null,
+        "description": null,
+        "banner": null,
+        "premium_tier": 0,
+        "premium_subscription_count": 0,
+        "preferred_locale": "en-US",
+        "public_updates_channel_id": null,
+    }))
+    .expect("valid guild");
+
+    Event::GuildCreate(Box::new(GuildCreate(guild)))
+}
@@ -0,0 +1,93 @@
+use rikka_rs::commands::{self, general::Ping, help::Help, logs::Logs, settings::Settings};
+use rikka_rs::reference::Reference;
+use rikka_rs::storage::MemoryStorage;
+use rikka_rs::Command;
+use std::sync::Arc;
+
+async fn reference() -> Reference {
+    std::env::set_var("LOG_HASH_NONCE", "test");
+    let logs = Logs::new(Arc::new(MemoryStorage::new())).await.unwrap();
+    let cmds: Vec<Arc<dyn Command>> = vec![
+        Arc::new(Ping {}),
+        Arc::new(Help {}),
+        Arc::new(Settings::new()),
+        Arc::new(logs),
+    ];
+
+    Reference::new("r.", &cmds)
+}
+
+#[tokio::test]
+async fn json_lists_commands_by_section() {
+    let json: serde_json::Value =
+        serde_json::from_str(&reference().await.to_json().unwrap()).unwrap();
+    assert_eq!(json["prefix"], "r.");
+
+    let sections = json["sections"].as_array().unwrap();
+    let names: Vec<_> = sections
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["General", "Moderation"]);
+
+    let general = &sections[0]["commands"];
+    assert_eq!(general[0]["name"], "ping");
+    assert_eq!(general[1]["name"], "help");
+    assert_eq!(general[1]["usage"], "[command] [--dm]");
+
+    let settings = &sections[1]["commands"][0];
+    assert_eq!(settings["aliases"][0], "config");
+    assert_eq!(settings["requires"][0], "Server only");
+    assert_eq!(settings["requires"][1], "Manage Guild");
+
+    let log = &sections[1]["commands"][1];
+    let enable = &log["subcommands"][0]["subcommands"][0];
+    assert_eq!(enable["name"], "log message enable");
+    assert_eq!(enable["usage"], "[channel]");
+    assert_eq!(log["subcommands"][0]["requires"][0], "Bot owner");
+}
+
+#[tokio::test]
+async fn markdown_nests_subcommands() {
+    let md = reference().await.to_markdown();
+    assert!(md.starts_with("# Command reference\n"));
+    assert!(md.contains("\n## General\n"));
+    assert!(md.contains("\n### `r.ping`\n\nChecks that the bot is alive\n"));
+    assert!(md.contains("- Usage: `r.help [command] [--dm]`\n"));
+    assert!(md.contains("\n#### `r.log message`\n"));
+    assert!(md.contains("\n##### `r.log message enable`\n"));
+    assert!(md.contains("- Examples: `r.log message enable`, `r.log message enable #logs`\n"));
+}
+
+#[test]
+fn every_command_is_documented_without_services() {
+    let reference = Reference::new("r.", &commands::reference());
+    let names: Vec<_> = reference
+        .sections
+        .iter()
+        .flat_map(|s| s.commands.iter().map(|c| c.name.as_str()))
+        .collect();
+
+    for name in &["ping", "help", "played", "log", "settings", "say"] {
+        assert!(names.contains(name), "{} is missing from {:?}", name, names);
+    }
+}
+
+#[test]
+fn every_command_has_a_description() {
+    fn check(entry: &rikka_rs::reference::Entry) {
+        assert!(
+            !entry.description.is_empty(),
+            "{} has no description in the catalog",
+            entry.name
+        );
+        entry.subcommands.iter().for_each(check);
+    }
+
+    let reference = Reference::new("r.", &commands::reference());
+    reference
+        .sections
+        .iter()
+        .flat_map(|s| s.commands.iter())
+        .for_each(check);
+}
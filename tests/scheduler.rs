@@ -0,0 +1,158 @@
+mod common;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use common::*;
+use rikka_rs::storage::MemoryStorage;
+use rikka_rs::{Command, CommandError, Job, Rikka};
+use std::sync::{Arc, Mutex};
+
+/// Records the payload of every job it runs.
+#[derive(Clone, Default)]
+struct Recorder {
+    runs: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn runs(&self) -> Vec<String> {
+        self.runs.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Command for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn job_kinds(&self) -> &'static [&'static str] {
+        &["record"]
+    }
+
+    async fn run_job(&self, _: &Rikka, job: &Job) -> Result<(), CommandError> {
+        self.runs.lock().unwrap().push(job.decode::<String>()?);
+        Ok(())
+    }
+}
+
+async fn harness(storage: MemoryStorage, rec: &Recorder) -> Harness {
+    let rec = rec.clone();
+    Harness::with_storage(storage, move |r| {
+        r.set_job_poll_interval(std::time::Duration::from_millis(20));
+        r.register_command(rec);
+    })
+    .await
+}
+
+#[tokio::test]
+async fn runs_due_jobs_once() {
+    let rec = Recorder::default();
+    let h = harness(MemoryStorage::new(), &rec).await;
+
+    let job = Job::new("record", Utc::now())
+        .payload(&"now".to_owned())
+        .unwrap();
+    h.bot.schedule(&job).await.unwrap();
+    let later = Job::new("record", Utc::now() + Duration::hours(1))
+        .payload(&"later".to_owned())
+        .unwrap();
+    h.bot.schedule(&later).await.unwrap();
+
+    h.settle().await;
+    assert_eq!(rec.runs(), vec!["now"]);
+    assert!(!h.bot.cancel_job(job.id).await.unwrap());
+    assert!(h.bot.cancel_job(later.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn cancelled_jobs_never_run() {
+    let rec = Recorder::default();
+    let h = harness(MemoryStorage::new(), &rec).await;
+
+    let job = Job::new("record", Utc::now() + Duration::milliseconds(100))
+        .payload(&"cancelled".to_owned())
+        .unwrap();
+    h.bot.schedule(&job).await.unwrap();
+    assert!(h.bot.cancel_job(job.id).await.unwrap());
+
+    h.settle().await;
+    assert!(rec.runs().is_empty());
+}
+
+#[tokio::test]
+async fn rescheduled_jobs_run_once() {
+    let rec = Recorder::default();
+    let h = harness(MemoryStorage::new(), &rec).await;
+
+    let job = Job::new("record", Utc::now() + Duration::milliseconds(100))
+        .payload(&"moved".to_owned())
+        .unwrap();
+    h.bot.schedule(&job).await.unwrap();
+    let moved = Job {
+        due: Utc::now(),
+        ..job.clone()
+    };
+    h.bot.schedule(&moved).await.unwrap();
+
+    h.settle().await;
+    tokio::time::delay_for(std::time::Duration::from_millis(200)).await;
+    h.settle().await;
+    assert_eq!(rec.runs(), vec!["moved"]);
+}
+
+#[tokio::test]
+async fn jobs_wait_for_disabled_commands() {
+    let rec = Recorder::default();
+    let h = harness(MemoryStorage::new(), &rec).await;
+    h.bot.set_command_enabled("recorder", false);
+
+    let job = Job::new("record", Utc::now())
+        .payload(&"waiting".to_owned())
+        .unwrap();
+    h.bot.schedule(&job).await.unwrap();
+
+    h.settle().await;
+    assert!(rec.runs().is_empty());
+    assert!(h.bot.cancel_job(job.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn recurring_jobs_reschedule() {
+    let rec = Recorder::default();
+    let h = harness(MemoryStorage::new(), &rec).await;
+
+    let job = Job::new("record", Utc::now())
+        .payload(&"tick".to_owned())
+        .unwrap()
+        .every(Duration::milliseconds(50));
+    h.bot.schedule(&job).await.unwrap();
+
+    h.settle().await;
+    assert!(h.bot.cancel_job(job.id).await.unwrap());
+    let runs = rec.runs().len();
+    assert!(runs >= 2, "ran {} times", runs);
+
+    h.settle().await;
+    assert_eq!(rec.runs().len(), runs);
+}
+
+#[tokio::test]
+async fn processes_sharing_storage_run_jobs_once() {
+    let storage = MemoryStorage::new();
+    let (a, b) = (Recorder::default(), Recorder::default());
+    let ha = harness(storage.clone(), &a).await;
+    let _hb = harness(storage, &b).await;
+
+    for i in 0..20 {
+        let job = Job::new("record", Utc::now())
+            .payload(&i.to_string())
+            .unwrap();
+        ha.bot.schedule(&job).await.unwrap();
+    }
+
+    ha.settle().await;
+    let mut runs = a.runs();
+    runs.extend(b.runs());
+    runs.sort_by_key(|r| r.parse::<u32>().unwrap());
+    assert_eq!(runs, (0..20).map(|i| i.to_string()).collect::<Vec<_>>());
+}